@@ -2,7 +2,9 @@
 use mimir_crypto::secp256k1::Address;
 use mimir_types::Bytes;
 use types::Error;
+use evm::DryRun;
 use project::contract::{ContractRole,ValidatorContract};
+use project::storage::Storage;
 use serde_json::{self,Value};
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -16,6 +18,9 @@ pub const FILE_NAME: &'static str = "chain.json";
 
 pub const TEMPLATE: &'static str = include_str!("../include/chain.json");
 
+/// Transitions of the byzantium EVM changes which affect constructors
+const BYZANTIUM_TRANSITIONS: &[&str] = &["eip140Transition","eip211Transition","eip214Transition"];
+
 
 /// Implementation target for the `ConfigModule` trait.
 pub struct Module;
@@ -36,19 +41,55 @@ impl ConfigModule for Module {
         // insert all genesis accounts, keeping track of whether or
         // not a validator contract was deployed.
         let mut validator_contract = false;
+        let mut dry_run = if ctx.options.skip_dry_run {
+            None
+        } else {
+            Some(DryRun::new(chain.gas_limit()?,chain.is_byzantium()?))
+        };
+        let mut constructors = Vec::new();
         for account in ctx.project.iter_accounts() {
             let (address,balance) = (account.address(),account.balance());
             if let Some(contract) = account.contract() {
-                if let Some(seeded_code) = contract.seed_args(&ctx.project)? {
-                    chain.insert_contract(address,&seeded_code,balance,contract.role)?;
+                if let Some(storage) = contract.storage.as_ref() {
+                    let runtime = contract.runtime.as_ref().ok_or_else(|| {
+                        let msg = format!("runtime code of `{}` is required for genesis storage (supply `{}.bin-runtime`, or a JSON artifact)",
+                            contract.name,contract.name);
+                        Error::message(msg)
+                    })?;
+                    chain.insert_initialized(address,runtime,storage,balance,contract.role)?;
+                    if let Some(dry_run) = dry_run.as_mut() {
+                        dry_run.insert_initialized(address,balance,runtime,storage);
+                    }
                 } else {
-                    chain.insert_contract(address,&contract.code,balance,contract.role)?;
+                    let code = contract.seed_args(&ctx.project)?
+                        .unwrap_or_else(|| contract.code.clone());
+                    chain.insert_contract(address,&code,balance,contract.role)?;
+                    constructors.push((contract.name.as_str(),address,balance,code));
                 }
                 if let Some(ContractRole::Validator(_)) = contract.role {
                     validator_contract = true;
                 }
             } else {
                 chain.insert_account(address,balance,None)?;
+                if let Some(dry_run) = dry_run.as_mut() {
+                    dry_run.insert_account(address,balance);
+                }
+            }
+        }
+        // execute constructors in address order (matching parity's genesis
+        // initialization), so that failures surface at build time.
+        if let Some(mut dry_run) = dry_run {
+            let max_code_size = chain.max_code_size()?;
+            constructors.sort_by_key(|(_,address,_,_)| address.into_inner());
+            for (name,address,balance,code) in constructors.iter() {
+                let report = dry_run.run_constructor(name,*address,*balance,code)?;
+                info!("constructor of `{}` used {} gas (runtime code is {} bytes)",
+                    name,report.gas_used,report.code_size);
+                if report.code_size == 0 {
+                    warn!("constructor of `{}` produced no runtime code",name);
+                } else if let Some(max) = max_code_size.filter(|max| report.code_size as u64 > *max) {
+                    warn!("runtime code of `{}` ({} bytes) exceeds `maxCodeSize` ({} bytes)",name,report.code_size,max);
+                }
             }
         }
         // if no validator contract was deployed, insert validator
@@ -104,6 +145,60 @@ impl ChainSpec {
         TEMPLATE.parse().expect("defaults must deserialize")
     }
 
+    /// Genesis block gas limit.
+    pub fn gas_limit(&self) -> Result<u64,Error> {
+        let raw = self.genesis.get("gasLimit").and_then(Value::as_str)
+            .ok_or_else(|| Error::message("chain spec must specify `genesis.gasLimit`"))?;
+        let gas_limit = u64::from_str_radix(raw.trim_left_matches("0x"),16)?;
+        Ok(gas_limit)
+    }
+
+    /// Chain id used for replay protection (`params.chainID`, or `params.networkID`).
+    pub fn chain_id(&self) -> Result<u64,Error> {
+        let raw = self.params.get("chainID").or_else(|| self.params.get("networkID"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::message("chain spec must specify `params.networkID`"))?;
+        let chain_id = u64::from_str_radix(raw.trim_left_matches("0x"),16)?;
+        Ok(chain_id)
+    }
+
+    /// Whether the byzantium EVM changes (EIPs 140, 211 & 214) are in effect at genesis.
+    pub fn is_byzantium(&self) -> Result<bool,Error> {
+        let mut enabled = Vec::new();
+        for key in BYZANTIUM_TRANSITIONS.iter() {
+            enabled.push(self.param_u64(key)? == Some(0));
+        }
+        if enabled.iter().all(|e| *e) {
+            Ok(true)
+        } else if enabled.iter().all(|e| !*e) {
+            Ok(false)
+        } else {
+            let msg = format!("constructors cannot be dry-run unless {} are all (or none) at genesis (see `--skip-dry-run`)",
+                BYZANTIUM_TRANSITIONS.join(", "));
+            Err(Error::message(msg))
+        }
+    }
+
+    /// Limit on the size of runtime code at genesis (parity has none unless `maxCodeSize` is set).
+    pub fn max_code_size(&self) -> Result<Option<u64>,Error> {
+        match self.param_u64("maxCodeSizeTransition")? {
+            Some(transition) if transition > 0 => Ok(None),
+            _ => self.param_u64("maxCodeSize"),
+        }
+    }
+
+    /// Numeric parameter (given as a hex string or a number).
+    fn param_u64(&self, key: &str) -> Result<Option<u64>,Error> {
+        match self.params.get(key) {
+            Some(Value::String(raw)) => Ok(Some(u64::from_str_radix(raw.trim_left_matches("0x"),16)?)),
+            Some(Value::Number(num)) => num.as_u64().map(Some).ok_or_else(|| {
+                Error::message(format!("invalid chain spec parameter `{}`",key))
+            }),
+            Some(_) => Err(Error::message(format!("invalid chain spec parameter `{}`",key))),
+            None => Ok(None),
+        }
+    }
+
     /// Insert validator set contract (alternative to validator list)
     pub fn set_validator_contract(&mut self, role: ValidatorContract, addr: Address) {
         let try_insert = |spec: &mut Value| -> Option<()> {
@@ -160,6 +255,26 @@ impl ChainSpec {
     /// insert a genesis contract
     pub fn insert_contract(&mut self, addr: Address, code: &Bytes, balance: u64, role: Option<ContractRole>) -> Result<(),Error> {
         self.insert_account(addr,balance,Some(code))?;
+        self.set_role(addr,role)
+    }
+
+    /// Insert a genesis contract in its initialized state (runtime code & storage)
+    pub fn insert_initialized(&mut self, addr: Address, runtime: &Bytes, storage: &Storage, balance: u64, role: Option<ContractRole>) -> Result<(),Error> {
+        if self.accounts.contains_key(&addr) {
+            let message = format!("multiple accounts specified for `{:?}`",addr);
+            return Err(Error::message(message));
+        }
+        let spec = json!({
+            "balance": balance.to_string(),
+            "code": runtime,
+            "storage": storage.to_json(),
+        });
+        let _ = self.accounts.insert(addr,spec);
+        self.set_role(addr,role)
+    }
+
+    /// Register a contract with a special role
+    fn set_role(&mut self, addr: Address, role: Option<ContractRole>) -> Result<(),Error> {
         match role {
             Some(ContractRole::Validator(role)) => {
                 self.set_validator_contract(role,addr);
@@ -1,10 +1,13 @@
 //! Build `docker-compose.yml` for project deployments
-use project::node::NodeRole;
-use types::Error;
-use std::collections::HashMap;
-use std::net::Ipv4Addr;
+use project::node::{Node,NodeRole};
+use project::network::{PortsConfig,PortService};
+use project::service::{ServiceOptions,LoggingConfig,HealthcheckConfig};
+use types::{EnodeAddr,Subnet,Error};
+use std::collections::{BTreeMap,HashMap,HashSet};
+use std::net::{IpAddr,Ipv4Addr};
+use serde_json::{Map,Value as JsonValue};
 use std::str::FromStr; 
-use serde_yaml::Value;
+use serde_yaml::{Value,Mapping};
 use serde_yaml;
 
 use config::ConfigModule;
@@ -25,36 +28,120 @@ impl ConfigModule for Module {
             } else {
                 DockerCompose::default()
             };
-            let mut expose_iface = config.expose_iface;
+            // w/o a port plan, `expose-iface` publishes the rpc ports of the first interface only
+            let (ports,max_published) = match config.ports.as_ref() {
+                Some(ports) => (ports.clone(),usize::max_value()),
+                None if config.expose_iface => (PortsConfig::default(),1),
+                None => (PortsConfig::default(),0),
+            };
+            let mut published = 0;
+            let mut host_ports = HashMap::new();
+            let mut endpoints = Map::new();
             let network_name = format!("{}-net",ctx.project.project_name());
-            for node in ctx.project.iter_nodes() {
+            let mut address_spaces = vec![(config.gateway(),config.subnet()?)];
+            if let (Some(gateway),Some(subnet)) = (config.ipv6_gateway_addr,config.ipv6_subnet()?) {
+                address_spaces.push((gateway.into(),subnet));
+            }
+            let mut assigned = HashMap::new();
+            let mut dependencies = BTreeMap::new();
+            let services = config.services.clone().unwrap_or_default();
+            for name in services.node.keys() {
+                if ctx.project.iter_nodes().filter_map(|n| n.internal()).all(|node| node.name() != name) {
+                    let msg = format!("service settings given for unknown node `{}`",name);
+                    return Err(Error::message(msg));
+                }
+            }
+            for node in ctx.project.iter_nodes().filter_map(|n| n.internal()) {
                 let service_name = node.name().to_string();
                 let state_volume = format!("{}-state",service_name);
-                let base_config = ServiceConfig::default()
+                let mut base_config = ServiceConfig::default()
                     .build_ctx(service_name.as_str())
-                    //.restart_policy("always")
-                    .volume(format!("{}:/{}/state",state_volume,util::CRATE_NAME))
-                    .ip_addr(network_name.as_str(),node.network_addr().ip().to_owned());
-                compose.add_volume(state_volume);
-                let service_config = match node.node_role() {
-                    NodeRole::Authority => {
-                        // TODO: decide on any authority-specific
-                        // configs if any (e.g. tags).
-                        base_config
-                    },
-                    NodeRole::Interface => {
-                        if expose_iface {
-                            expose_iface = false;
-                            info!("exposing local RPC for interface `{}`",node.name());
-                            base_config.local_port(8545).local_port(8546)
+                    .volume(format!("{}:/{}/state",state_volume,util::CRATE_NAME));
+                let options = services.options(&Node::from(node));
+                let v3 = compose.is_v3()?;
+                if !v3 && (options.is_healthchecked() || options.depends_on.is_some() || options.cpus.is_some()) {
+                    // healthchecks, dependency conditions & cpu limits were added in 2.1 & 2.2
+                    compose.require_version(2)?;
+                }
+                base_config = base_config.options(&options);
+                if options.is_healthchecked() {
+                    let command = services.healthcheck.command(RPC_PORT);
+                    base_config = base_config.healthcheck(command,&services.healthcheck);
+                }
+                for dependency in ctx.project.iter_nodes().filter(|n| n.internal().is_some()) {
+                    if dependency.name() != node.name() && options.depends_on(&dependency) {
+                        let condition = if services.options(&dependency).is_healthchecked() {
+                            "service_healthy"
                         } else {
-                            base_config
-                        }
-                    },
+                            "service_started"
+                        };
+                        base_config = base_config.depends_on(dependency.name(),condition);
+                        dependencies.entry(node.name().to_owned()).or_insert_with(Vec::new)
+                            .push(dependency.name().to_owned());
+                    }
+                }
+                if let Some(networks) = ctx.project.compose_networks(node.name()) {
+                    for (network,ip_addr) in networks.iter() {
+                        base_config = base_config.ip_addr(network.as_str(),*ip_addr);
+                    }
+                } else {
+                    let ip_addr = node.network_addr().ip();
+                    check_addr(node.name(),ip_addr,&address_spaces)?;
+                    if let Some(other) = assigned.insert(ip_addr,node.name()) {
+                        let msg = format!("nodes `{}` and `{}` share the address `{}`",other,node.name(),ip_addr);
+                        return Err(Error::message(msg));
+                    }
+                    base_config = base_config.ip_addr(network_name.as_str(),ip_addr);
+                }
+                compose.add_volume(state_volume);
+                let publish = published < max_published && match node.node_role() {
+                    NodeRole::Authority => ports.authorities,
+                    NodeRole::Interface => true,
                 };
-                compose.add_service(service_name,service_config);
+                if publish {
+                    info!("publishing ports of `{}`",node.name());
+                    let host = if ports.bind.is_unspecified() { Ipv4Addr::new(127,0,0,1) } else { ports.bind };
+                    let mut node_endpoints = Map::new();
+                    for service in ports.services.iter() {
+                        let host_port = ports.host_port(node.name(),published,*service)?;
+                        if let Some(other) = host_ports.insert(host_port,node.name()) {
+                            let msg = format!("host port {} of `{}` is already used by `{}`",
+                                host_port,node.name(),other);
+                            return Err(Error::message(msg));
+                        }
+                        let (container_port,endpoint) = match service {
+                            PortService::Rpc => (RPC_PORT,format!("http://{}:{}",host,host_port)),
+                            PortService::Ws => (WS_PORT,format!("ws://{}:{}",host,host_port)),
+                            PortService::P2p => {
+                                let enode = node.enode_addr_at(host.into());
+                                let enode = EnodeAddr { port: host_port, ..enode };
+                                (node.network_addr().port(),enode.to_string())
+                            },
+                        };
+                        base_config = base_config.publish(ports.bind,host_port,container_port);
+                        if *service == PortService::P2p {
+                            base_config = base_config.publish_udp(ports.bind,host_port,container_port);
+                        }
+                        node_endpoints.insert(service.to_string(),JsonValue::String(endpoint));
+                    }
+                    endpoints.insert(node.name().to_owned(),JsonValue::Object(node_endpoints));
+                    published += 1;
+                }
+                if v3 {
+                    base_config = base_config.into_v3(node.name());
+                }
+                compose.add_service(service_name,base_config)?;
             }
-            compose.add_network(network_name,config.gateway_addr)?;
+            check_dependencies(&dependencies)?;
+            if !assigned.is_empty() {
+                for (gateway,subnet) in address_spaces {
+                    compose.add_network(network_name.clone(),gateway,subnet)?;
+                }
+            }
+            for network in config.networks.iter() {
+                compose.add_network(network.name.clone(),network.gateway(),network.subnet)?;
+            }
+            ctx.build_files.project().insert_json(ENDPOINTS_FILENAME,&endpoints)?;
             ctx.build_files.project().insert_yaml(FILE_NAME,&compose)
         } else {
             Ok(())
@@ -65,14 +152,56 @@ impl ConfigModule for Module {
 
 const FILE_NAME: &'static str = "docker-compose.yml";
 
+/// Host endpoints of all published nodes
+const ENDPOINTS_FILENAME: &'static str = "endpoints.json";
+
+/// Container port of parity's JSON-RPC server
+pub const RPC_PORT: u16 = 8545;
+
+/// Container port of parity's websockets server
+pub const WS_PORT: u16 = 8546;
+
+
+/// Check that `addr` is a free host address of the address space of the same family.
+fn check_addr(name: &str, addr: IpAddr, address_spaces: &[(IpAddr,Subnet)]) -> Result<(),Error> {
+    match address_spaces.iter().find(|(_,subnet)| subnet.is_ipv4() == addr.is_ipv4()) {
+        Some((gateway,subnet)) => {
+            if subnet.is_host(addr) && addr != *gateway {
+                Ok(())
+            } else {
+                let msg = format!("address `{}` of node `{}` is not a free host address of `{}`",addr,name,subnet);
+                Err(Error::message(msg))
+            }
+        },
+        None => {
+            let msg = format!("node `{}` has an ipv6 address, but `ipv6-gateway-addr` is not set",name);
+            Err(Error::message(msg))
+        },
+    }
+}
+
 
 /// Configuration of a service
 #[derive(Default,Debug,Clone,Serialize,Deserialize)]
 struct ServiceConfig {
     #[serde(default,skip_serializing_if = "HashMap::is_empty")]
     build: HashMap<String,Value>,
-    //#[serde(default,skip_serializing_if = "Option::is_none")]
-    //restart: Option<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    restart: Option<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<Healthcheck>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    mem_limit: Option<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    cpus: Option<f64>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    logging: Option<LoggingConfig>,
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String,String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    depends_on: Option<DependsOn>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    deploy: Option<Value>,
     #[serde(default,skip_serializing_if = "Vec::is_empty")]
     volumes: Vec<String>,
     #[serde(default,skip_serializing_if = "Vec::is_empty")]
@@ -90,15 +219,70 @@ impl ServiceConfig {
         self.build.insert("context".to_owned(),Value::String(ctx.into())); self
     }
 
-    //pub fn restart_policy(mut self, policy: impl Into<String>) -> Self { self.restart = Some(policy.into()); self }
+    /// Apply the restart policy, limits, logging & labels of `options`.
+    pub fn options(mut self, options: &ServiceOptions) -> Self {
+        if let Some(policy) = options.restart { self.restart = Some(policy.to_string()); }
+        if let Some(memory) = options.memory.as_ref() { self.mem_limit = Some(memory.to_owned()); }
+        if let Some(cpus) = options.cpus { self.cpus = Some(cpus); }
+        if let Some(logging) = options.logging.as_ref() { self.logging = Some(logging.clone()); }
+        self.labels.extend(options.labels.iter().map(|(k,v)| (k.clone(),v.clone())));
+        self
+    }
+
+    pub fn healthcheck(mut self, command: String, config: &HealthcheckConfig) -> Self {
+        self.healthcheck = Some(Healthcheck {
+            test: vec!["CMD-SHELL".to_owned(),command],
+            interval: config.interval.clone(),
+            timeout: config.timeout.clone(),
+            retries: config.retries,
+        });
+        self
+    }
+
+    pub fn depends_on(mut self, service: impl Into<String>, condition: impl Into<String>) -> Self {
+        let dependency = Dependency { condition: condition.into() };
+        match self.depends_on.get_or_insert_with(|| DependsOn::Long(Default::default())) {
+            DependsOn::Long(dependencies) => { dependencies.insert(service.into(),dependency); },
+            DependsOn::Short(dependencies) => { dependencies.push(service.into()); },
+        }
+        self
+    }
+
+    /// Convert to the version 3 file format, which moves resource limits under `deploy`
+    /// and has no dependency conditions.
+    pub fn into_v3(mut self, name: &str) -> Self {
+        let limits: Mapping = self.mem_limit.take().map(|memory| ("memory",memory))
+            .into_iter()
+            .chain(self.cpus.take().map(|cpus| ("cpus",cpus.to_string())))
+            .map(|(k,v)| (Value::from(k),Value::from(v)))
+            .collect();
+        if limits.len() > 0 {
+            let resources: Mapping = Some((Value::from("limits"),Value::Mapping(limits))).into_iter().collect();
+            let deploy: Mapping = Some((Value::from("resources"),Value::Mapping(resources))).into_iter().collect();
+            self.deploy = Some(Value::Mapping(deploy));
+        }
+        if let Some(DependsOn::Long(dependencies)) = self.depends_on.take() {
+            if dependencies.values().any(|dependency| dependency.condition != "service_started") {
+                warn!("dependency conditions of `{}` are not supported by compose file version 3",name);
+            }
+            self.depends_on = Some(DependsOn::Short(dependencies.into_iter().map(|(k,_)| k).collect()));
+        }
+        self
+    }
 
     pub fn volume(mut self, volume: impl Into<String>) -> Self { self.volumes.push(volume.into()); self }
 
-    pub fn local_port(mut self, port: u16) -> Self { self.ports.push(format!("127.0.0.1:{0}:{0}",port)); self }
+    pub fn publish(mut self, bind: Ipv4Addr, host_port: u16, port: u16) -> Self {
+        self.ports.push(format!("{}:{}:{}",bind,host_port,port)); self
+    }
 
-    pub fn ip_addr(mut self, network: impl Into<String>, gateway: Ipv4Addr) -> Self {
-        let key = "ipv4_address".to_string();
-        let val = Value::String(gateway.to_string());
+    pub fn publish_udp(mut self, bind: Ipv4Addr, host_port: u16, port: u16) -> Self {
+        self.ports.push(format!("{}:{}:{}/udp",bind,host_port,port)); self
+    }
+
+    pub fn ip_addr(mut self, network: impl Into<String>, addr: IpAddr) -> Self {
+        let key = if addr.is_ipv4() { "ipv4_address" } else { "ipv6_address" }.to_string();
+        let val = Value::String(addr.to_string());
         self.networks.entry(network.into())
             .or_default()
             .insert(key,val);
@@ -107,18 +291,50 @@ impl ServiceConfig {
 }
 
 
+/// Healthcheck of a service
+#[derive(Debug,Clone,Serialize,Deserialize)]
+struct Healthcheck {
+    test: Vec<String>,
+    interval: String,
+    timeout: String,
+    retries: u32,
+}
+
+
+/// Dependencies of a service, as a list of services (short form) or by service (long form)
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(untagged)]
+enum DependsOn {
+    Short(Vec<String>),
+    Long(BTreeMap<String,Dependency>),
+}
+
+
+/// Long-form dependency of a service upon another service
+#[derive(Debug,Clone,Serialize,Deserialize)]
+struct Dependency {
+    condition: String,
+}
+
+
 /// docker-compose config file
+///
+/// Services, networks & volumes are kept as raw values so that any template settings
+/// (including those unknown to `pib`) survive the merge with the generated settings.
+/// Unknown top-level keys (e.g. `secrets`, `configs` & `x-` extension fields) are
+/// passed through unchanged.
 #[derive(Debug,Clone,Serialize,Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct DockerCompose {
     #[serde(default = "version_two")]
     version: String,
     #[serde(default)]
-    services: HashMap<String,ServiceConfig>,
-    #[serde(default)]
-    networks: HashMap<String,HashMap<String,HashMap<String,Value>>>,
-    #[serde(default)]
-    volumes: HashMap<String,HashMap<String,Value>>,
+    services: HashMap<String,Value>,
+    #[serde(default,skip_serializing_if = "HashMap::is_empty")]
+    networks: HashMap<String,Value>,
+    #[serde(default,skip_serializing_if = "HashMap::is_empty")]
+    volumes: HashMap<String,Value>,
+    #[serde(flatten)]
+    ext: BTreeMap<String,Value>,
 }
 
 
@@ -129,8 +345,8 @@ impl Default for DockerCompose {
 
     fn default() -> Self {
         let version = version_two();
-        let (services,networks,volumes) = Default::default();
-        Self { version, services, networks, volumes }
+        let (services,networks,volumes,ext) = Default::default();
+        Self { version, services, networks, volumes, ext }
     }
 }
 
@@ -147,26 +363,283 @@ impl FromStr for DockerCompose {
 
 impl DockerCompose {
 
-    fn add_service(&mut self, name: String, config: ServiceConfig) {
-        self.services.insert(name,config);
+    /// Add a generated service, merging it into the template service of the same name (if any).
+    fn add_service(&mut self, name: String, config: ServiceConfig) -> Result<(),Error> {
+        let generated = serde_yaml::to_value(&config)?;
+        match self.services.get_mut(&name) {
+            Some(template) => {
+                if !template.is_mapping() && !template.is_null() {
+                    let msg = format!("template service `{}` must be a mapping",name);
+                    return Err(Error::message(msg));
+                }
+                normalize_service(template,&generated);
+                merge_value(template,generated);
+                return Ok(());
+            },
+            None => { },
+        }
+        self.services.insert(name,generated);
+        Ok(())
+    }
+
+    /// Major & minor file format version.
+    fn version(&self) -> Result<(u32,u32),Error> {
+        let mut split = self.version.trim().splitn(2,'.');
+        let major = split.next().and_then(|v| v.parse().ok());
+        let minor = split.next().map(|v| v.parse().ok()).unwrap_or(Some(0));
+        match (major,minor) {
+            (Some(major),Some(minor)) if major == 2 || major == 3 => Ok((major,minor)),
+            _ => {
+                let msg = format!("unsupported compose file version `{}` (expected 2.x or 3.x)",self.version);
+                Err(Error::message(msg))
+            },
+        }
+    }
+
+    fn is_v3(&self) -> Result<bool,Error> {
+        Ok(self.version()?.0 == 3)
     }
 
-    fn add_network(&mut self, name: String, gateway: Ipv4Addr) -> Result<(),Error> {
-        let addr_space = Some(("gateway",gateway.to_string())).into_iter()
-            .chain(Some(("subnet",format!("{}/24",gateway))))
+    /// Add a subnet to the named network.
+    ///
+    /// The version 3 file format only accepts a `subnet`, so the gateway must then be the
+    /// conventional one (i.e. the one docker assigns by default).
+    fn add_network(&mut self, name: String, gateway: IpAddr, subnet: Subnet) -> Result<(),Error> {
+        let v3 = self.is_v3()?;
+        if gateway.is_ipv6() && v3 {
+            let msg = format!("ipv6 network `{}` requires compose file version 2.x",name);
+            return Err(Error::message(msg));
+        }
+        if v3 && gateway != subnet.gateway() {
+            let msg = format!("gateway `{}` of network `{}` requires compose file version 2.x (version 3 only supports `{}`)",
+                gateway,name,subnet.gateway());
+            return Err(Error::message(msg));
+        }
+        let gateway_entry = if v3 { None } else { Some(("gateway",gateway.to_string())) };
+        let addr_space = gateway_entry.into_iter()
+            .chain(Some(("subnet",subnet.to_string())))
             .map(|(k,v)| (Value::from(k),Value::from(v)))
             .collect();
-        self.networks.entry(name).or_default()
-            .entry("ipam".to_string()).or_default()
-            .entry("config".to_string())
-            .or_insert_with(|| Value::Sequence(vec![])).as_sequence_mut()
+        let network = self.networks.entry(name.clone()).or_insert(Value::Null);
+        if network.is_null() {
+            *network = Value::Mapping(Mapping::new());
+        }
+        let network = network.as_mapping_mut().ok_or_else(|| {
+            Error::message(format!("network `{}` must be mapping",name))
+        })?;
+        if gateway.is_ipv6() {
+            network.insert(Value::from("enable_ipv6"),Value::Bool(true));
+        }
+        let ipam_key = Value::from("ipam");
+        if !network.contains_key(&ipam_key) {
+            network.insert(ipam_key.clone(),Value::Mapping(Mapping::new()));
+        }
+        let ipam = network.get_mut(&ipam_key).and_then(Value::as_mapping_mut)
+            .ok_or_else(|| Error::message("field `ipam` must be mapping"))?;
+        let config_key = Value::from("config");
+        if !ipam.contains_key(&config_key) {
+            ipam.insert(config_key.clone(),Value::Sequence(vec![]));
+        }
+        ipam.get_mut(&config_key).and_then(Value::as_sequence_mut)
             .ok_or_else(|| Error::message("field `config` must be sequence"))?
             .push(Value::Mapping(addr_space));
         Ok(())
     }
 
+    /// Raise the file format version to at least `2.<minor>`.
+    fn require_version(&mut self, minor: u32) -> Result<(),Error> {
+        match self.version()? {
+            (2,current) => {
+                if current < minor {
+                    info!("raising compose file version from `{}` to `2.{}`",self.version,minor);
+                    self.version = format!("2.{}",minor);
+                }
+                Ok(())
+            },
+            _ => {
+                let msg = format!("service settings require compose file version 2.{} or later (found `{}`)",minor,self.version);
+                Err(Error::message(msg))
+            },
+        }
+    }
+
     fn add_volume(&mut self, name: String) {
-        self.volumes.insert(name,Default::default());
+        self.volumes.entry(name).or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+}
+
+
+/// Check that the dependencies of the services form no cycle (which compose only rejects
+/// once the services are started).
+fn check_dependencies(dependencies: &BTreeMap<String,Vec<String>>) -> Result<(),Error> {
+    fn visit<'a>(name: &'a str, dependencies: &'a BTreeMap<String,Vec<String>>,
+                 path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>) -> Result<(),Error> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if let Some(index) = path.iter().position(|other| *other == name) {
+            let cycle: Vec<&str> = path[index..].iter().cloned().chain(Some(name)).collect();
+            let msg = format!("`depends-on` settings form a cycle ({})",cycle.join(" -> "));
+            return Err(Error::message(msg));
+        }
+        path.push(name);
+        for dependency in dependencies.get(name).into_iter().flat_map(|names| names.iter()) {
+            visit(dependency,dependencies,path,done)?;
+        }
+        path.pop();
+        done.insert(name);
+        Ok(())
+    }
+    let (mut path,mut done) = (Vec::new(),HashSet::new());
+    for name in dependencies.keys() {
+        visit(name,dependencies,&mut path,&mut done)?;
+    }
+    Ok(())
+}
+
+
+/// Convert short-form (list) settings of a template service to the long (mapping) form
+/// where the generated service uses the long form, so that the two may be merged.
+fn normalize_service(template: &mut Value, generated: &Value) {
+    let (template,generated) = match (template.as_mapping_mut(),generated.as_mapping()) {
+        (Some(template),Some(generated)) => (template,generated),
+        _ => return,
+    };
+    for (key,value) in generated.iter() {
+        let name = match key.as_str() { Some(name) => name, None => continue };
+        if !value.is_mapping() { continue; }
+        let entries = match template.get(key).and_then(Value::as_sequence) {
+            Some(entries) => entries.clone(),
+            None => continue,
+        };
+        let mapping = entries.iter().filter_map(Value::as_str).map(|entry| {
+            match name {
+                "labels" | "environment" => {
+                    let mut split = entry.splitn(2,'=');
+                    let key = split.next().unwrap_or(entry);
+                    (Value::from(key),split.next().map(Value::from).unwrap_or(Value::Null))
+                },
+                "depends_on" => {
+                    let condition = Some((Value::from("condition"),Value::from("service_started")));
+                    (Value::from(entry),Value::Mapping(condition.into_iter().collect()))
+                },
+                _ => (Value::from(entry),Value::Null),
+            }
+        }).collect();
+        template.insert(key.clone(),Value::Mapping(mapping));
+    }
+}
+
+
+/// Merge `generated` into `template`: mappings are merged recursively, sequences are
+/// extended with any new elements & all other values are replaced.
+fn merge_value(template: &mut Value, generated: Value) {
+    match generated {
+        Value::Mapping(generated) => {
+            if let Some(template) = template.as_mapping_mut() {
+                for (key,value) in generated {
+                    if !template.contains_key(&key) {
+                        template.insert(key,value);
+                    } else if let Some(existing) = template.get_mut(&key) {
+                        merge_value(existing,value);
+                    }
+                }
+                return;
+            }
+            *template = Value::Mapping(generated);
+        },
+        Value::Sequence(generated) => {
+            if let Some(template) = template.as_sequence_mut() {
+                for value in generated {
+                    if !template.contains(&value) {
+                        template.push(value);
+                    }
+                }
+                return;
+            }
+            *template = Value::Sequence(generated);
+        },
+        generated => { *template = generated; },
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use serde_yaml::{self,Value};
+    use std::collections::BTreeMap;
+    use super::{merge_value,normalize_service,check_dependencies};
+
+    fn yaml(s: &str) -> Value { serde_yaml::from_str(s).unwrap() }
+
+    #[test]
+    fn merge_replaces_scalars() {
+        let mut template = yaml("image: custom\nrestart: always\n");
+        merge_value(&mut template,yaml("image: generated\ncontainer_name: node-0\n"));
+        assert_eq!(template,yaml("image: generated\nrestart: always\ncontainer_name: node-0\n"));
+    }
+
+    #[test]
+    fn merge_nested_mappings() {
+        let mut template = yaml("labels:\n  team: infra\n");
+        merge_value(&mut template,yaml("labels:\n  pib/node: node-0\n"));
+        assert_eq!(template,yaml("labels:\n  team: infra\n  pib/node: node-0\n"));
+    }
+
+    #[test]
+    fn merge_sequences_without_duplicates() {
+        let mut template = yaml("ports: ['8545:8545', '30303:30303']\n");
+        merge_value(&mut template,yaml("ports: ['30303:30303', '8546:8546']\n"));
+        assert_eq!(template,yaml("ports: ['8545:8545', '30303:30303', '8546:8546']\n"));
+    }
+
+    #[test]
+    fn merge_replaces_mismatched_kinds() {
+        let mut template = yaml("networks: default\n");
+        merge_value(&mut template,yaml("networks:\n  pib-net: {}\n"));
+        assert_eq!(template,yaml("networks:\n  pib-net: {}\n"));
+    }
+
+    #[test]
+    fn normalize_short_form_labels() {
+        let mut template = yaml("labels: ['team=infra', 'flag']\nenvironment: ['A=1']\n");
+        normalize_service(&mut template,&yaml("labels:\n  pib/node: node-0\nenvironment:\n  B: '2'\n"));
+        assert_eq!(template,yaml("labels:\n  team: infra\n  flag: ~\nenvironment:\n  A: '1'\n"));
+    }
+
+    #[test]
+    fn normalize_short_form_depends_on() {
+        let mut template = yaml("depends_on: [node-1]\n");
+        normalize_service(&mut template,&yaml("depends_on:\n  node-2:\n    condition: service_healthy\n"));
+        assert_eq!(template,yaml("depends_on:\n  node-1:\n    condition: service_started\n"));
+    }
+
+    #[test]
+    fn normalize_leaves_matching_forms() {
+        let mut template = yaml("ports: ['8545:8545']\nlabels:\n  team: infra\n");
+        let expected = template.clone();
+        normalize_service(&mut template,&yaml("ports: ['30303:30303']\nlabels:\n  pib/node: node-0\n"));
+        assert_eq!(template,expected);
+    }
+
+    fn dependencies(edges: &[(&str,&str)]) -> BTreeMap<String,Vec<String>> {
+        let mut dependencies = BTreeMap::new();
+        for (service,dependency) in edges.iter() {
+            dependencies.entry(service.to_string()).or_insert_with(Vec::new).push(dependency.to_string());
+        }
+        dependencies
+    }
+
+    #[test]
+    fn accepts_acyclic_dependencies() {
+        let edges = [("node-2","node-1"),("node-2","node-0"),("node-1","node-0")];
+        assert!(check_dependencies(&dependencies(&edges)).is_ok());
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let edges = [("node-0","node-1"),("node-1","node-2"),("node-2","node-0")];
+        let err = check_dependencies(&dependencies(&edges)).err().expect("cycle must be rejected");
+        assert!(err.to_string().contains("node-0 -> node-1 -> node-2 -> node-0"),"unexpected error: {}",err);
+    }
+}
@@ -1,6 +1,7 @@
-use project::SetupContext;
+use project::{SetupContext,BuildContext};
 use config::ConfigModule;
 use types::Error;
+use util;
 
 /// Source of example/debug contract
 pub const EXAMPLE_SOURCE: &str = include_str!("../include/EchoContract.sol");
@@ -30,5 +31,24 @@ impl ConfigModule for Module {
             Ok(())
         }
     }
+
+    fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
+        if ctx.options.export_contracts {
+            for contract in ctx.project.iter_contracts() {
+                let code = util::hex_string(&contract.code.clone().into_inner());
+                let bin_file = format!("{}/{}.bin",EXPORT_DIR,contract.name);
+                ctx.build_files.project().insert(bin_file,code);
+                if let Some(abi) = contract.abi.as_ref() {
+                    let abi_file = format!("{}/{}.abi",EXPORT_DIR,contract.name);
+                    ctx.build_files.project().insert_json(abi_file,abi.json())?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
+
+/// Output directory of exported contract artifacts
+const EXPORT_DIR: &str = "contracts";
+
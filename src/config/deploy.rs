@@ -0,0 +1,76 @@
+//! Sign post-genesis deployments & build their replay script
+use types::Error;
+use config::chain::{self,ChainSpec};
+use std::fmt::Write;
+
+
+use config::ConfigModule;
+use project::{SetupContext,BuildContext};
+
+/// Implementation target for the `ConfigModule` trait.
+pub struct Module;
+
+
+impl ConfigModule for Module {
+
+    fn setup(&self, ctx: &mut SetupContext) -> Result<(),Error> {
+        ctx.files.templates_mut().insert(SCRIPT_FILENAME,SCRIPT_TEMPLATE);
+        Ok(())
+    }
+
+    fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
+        let node = match ctx.project.deployment_node() {
+            Some(node) => node,
+            None if ctx.project.iter_deployments().next().is_some() => {
+                return Err(Error::message("deployments require an internal interface node"));
+            },
+            None => return Ok(()),
+        };
+        let chain: ChainSpec = ctx.project_files.templates().get(chain::FILE_NAME)
+            .unwrap_or(chain::TEMPLATE)
+            .parse()?;
+        let chain_id = chain.chain_id()?;
+        let mut tx_file = String::new();
+        let mut summary = Vec::new();
+        for deployment in ctx.project.iter_deployments() {
+            let txs = deployment.sign(&ctx.project,chain_id)?;
+            let mut tx_summary = Vec::new();
+            for tx in txs.iter() {
+                writeln!(tx_file,"{} {}",tx.label,tx.signed.raw_hex())?;
+                tx_summary.push(json!({
+                    "label": tx.label,
+                    "nonce": tx.nonce,
+                    "hash": tx.signed.hash_hex(),
+                }));
+            }
+            info!("signed {} transaction(s) deploying `{}` at {}",
+                txs.len(),deployment.contract.name,deployment.contract.addr);
+            summary.push(json!({
+                "name": deployment.contract.name,
+                "address": deployment.contract.addr,
+                "deployer": deployment.deployer,
+                "transactions": tx_summary,
+            }));
+        }
+        let script: &str = ctx.project_files.templates().get(SCRIPT_FILENAME)
+            .unwrap_or(SCRIPT_TEMPLATE);
+        ctx.build_files.node(node.name()).config().insert(TX_FILENAME,tx_file);
+        ctx.build_files.node(node.name()).config().insert(SCRIPT_FILENAME,script);
+        ctx.build_files.project().insert_json(SUMMARY_FILENAME,&summary)?;
+        Ok(())
+    }
+}
+
+
+pub const SCRIPT_FILENAME: &'static str = "deploy.sh";
+
+pub const SCRIPT_TEMPLATE: &'static str = include_str!("../include/deploy.sh");
+
+/// Raw transactions, one `<label> <raw-tx>` pair per line
+pub const TX_FILENAME: &'static str = "deployments.txt";
+
+/// Addresses & transaction hashes of all deployments
+pub const SUMMARY_FILENAME: &'static str = "deployments.json";
+
+/// Dockerfile instruction which submits deployments before starting parity
+pub const ENTRYPOINT: &'static str = r#"ENTRYPOINT ["/bin/bash","/pib/deploy.sh"]"#;
@@ -0,0 +1,186 @@
+/// Build host (non-docker) installs for each node
+use project::HostConfig;
+use types::Error;
+use config::{parity,scripts};
+use std::path::{Path,PathBuf};
+use std::fmt::Write;
+use toml;
+
+use config::ConfigModule;
+use project::{SetupContext,BuildContext};
+
+/// Implementation target for the `ConfigModule` trait.
+pub struct Module;
+
+
+impl ConfigModule for Module {
+
+    fn setup(&self, ctx: &mut SetupContext) -> Result<(),Error> {
+        ctx.files.templates_mut().insert(INSTALL_FILENAME,INSTALL_TEMPLATE);
+        Ok(())
+    }
+
+    fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
+        let project = ctx.project;
+        let host = match project.host_config() {
+            Some(host) => host,
+            None => return Ok(()),
+        };
+        let install_template: &str = ctx.project_files.templates().get(INSTALL_FILENAME)
+            .unwrap_or(INSTALL_TEMPLATE);
+        let deployment_node = project.deployment_node().map(|n| n.name());
+        let nodes = project.iter_nodes()
+            .filter(|node| host.nodes.as_ref().map(|tag| node.tags().contains(tag)).unwrap_or(true))
+            .filter_map(|node| node.internal());
+        for node in nodes {
+            let name = node.name();
+            if deployment_node == Some(name) {
+                warn!("deployments of `{}` are not carried into its host install",name);
+            }
+            let files: Vec<(PathBuf,String)> = ctx.build_files.node(name).config().iter()
+                .map(|(path,buff)| (path.to_owned(),buff.to_owned()))
+                .collect();
+            let dir = Path::new(DIR_NAME).join(name);
+            for (path,buff) in files {
+                if path == Path::new(scripts::INIT_FILENAME) {
+                    // superseded by the install script
+                    continue;
+                } else if path == Path::new(parity::FILE_NAME) {
+                    let config = host_parity_config(host,name,&buff)?;
+                    ctx.build_files.project().insert(dir.join(path),config);
+                } else {
+                    ctx.build_files.project().insert(dir.join(path),buff);
+                }
+            }
+            let unit_file = unit_file_name(name);
+            ctx.build_files.project().insert(dir.join(&unit_file),unit(host,project.project_name(),name));
+            // the variables follow the shebang (if any), which must remain the first line
+            let (shebang,body) = if install_template.starts_with("#!") {
+                let end = install_template.find('\n').map(|i| i + 1).unwrap_or(install_template.len());
+                install_template.split_at(end)
+            } else {
+                ("",install_template)
+            };
+            let mut install = String::from(shebang);
+            if !install.is_empty() && !install.ends_with('\n') {
+                install.push('\n');
+            }
+            writeln!(install,"CONFIG_DIR={}",shell_quote(&host.config_dir(name)))?;
+            writeln!(install,"STATE_DIR={}",shell_quote(&host.state_dir(name)))?;
+            writeln!(install,"SERVICE_USER={}",shell_quote(&host.user))?;
+            writeln!(install,"UNIT_FILE={}",shell_quote(&unit_file))?;
+            install.push_str(body);
+            ctx.build_files.project().insert(dir.join(INSTALL_FILENAME),install);
+        }
+        Ok(())
+    }
+}
+
+
+pub const INSTALL_FILENAME: &'static str = "host-install.sh";
+
+pub const INSTALL_TEMPLATE: &'static str = include_str!("../include/host-install.sh");
+
+const DIR_NAME: &'static str = "host";
+
+/// Container-relative prefixes of the paths which belong under a node's state directory
+const STATE_PREFIXES: &[(&str,&str)] = &[
+    ("pib/state/",""),
+    ("pib/keys","keys"),
+];
+
+/// Container-relative prefix of all other paths (which belong under the config directory)
+const CONFIG_PREFIX: &'static str = "pib/";
+
+
+fn unit_file_name(node: &str) -> String { format!("pib-{}.service",node) }
+
+
+/// Quote `value` as a single shell word (within single quotes, only `'` needs escaping).
+fn shell_quote(value: &str) -> String {
+    format!("'{}'",value.replace('\'',"'\\''"))
+}
+
+
+fn unit(host: &HostConfig, project_name: &str, node: &str) -> String {
+    format!(
+r##"[Unit]
+Description=pib node `{node}` ({project})
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+User={user}
+Group={user}
+WorkingDirectory={state}
+ExecStart={parity} --config {config}/{config_file}
+Restart=on-failure
+
+[Install]
+WantedBy=multi-user.target
+"##,
+        node = node,
+        project = project_name,
+        user = host.user,
+        state = host.state_dir(node),
+        parity = host.parity,
+        config = host.config_dir(node),
+        config_file = parity::FILE_NAME,
+    )
+}
+
+
+/// Render a generated parity config with absolute host paths in place of the
+/// container-relative `pib/...` paths.
+fn host_parity_config(host: &HostConfig, node: &str, buff: &str) -> Result<String,Error> {
+    let mut config: toml::Value = toml::from_str(buff)?;
+    rewrite_paths(host,node,&mut config);
+    Ok(toml::to_string(&config)?)
+}
+
+
+fn rewrite_paths(host: &HostConfig, node: &str, value: &mut toml::Value) {
+    match value {
+        toml::Value::String(path) => {
+            if let Some(host_path) = host_path(host,node,path) {
+                *path = host_path;
+            }
+        },
+        toml::Value::Array(values) => {
+            for value in values.iter_mut() { rewrite_paths(host,node,value); }
+        },
+        toml::Value::Table(table) => {
+            for (_,value) in table.iter_mut() { rewrite_paths(host,node,value); }
+        },
+        _ => { },
+    }
+}
+
+
+fn host_path(host: &HostConfig, node: &str, path: &str) -> Option<String> {
+    for (prefix,replacement) in STATE_PREFIXES.iter() {
+        if path.starts_with(prefix) {
+            let suffix = format!("{}{}",replacement,&path[prefix.len()..]);
+            return Some(format!("{}/{}",host.state_dir(node),suffix));
+        }
+    }
+    if path.starts_with(CONFIG_PREFIX) {
+        Some(format!("{}/{}",host.config_dir(node),&path[CONFIG_PREFIX.len()..]))
+    } else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::shell_quote;
+
+    #[test]
+    fn quotes_shell_words() {
+        assert_eq!(shell_quote("/var/lib/pib/node-0"),"'/var/lib/pib/node-0'");
+        assert_eq!(shell_quote("$HOME/`id`"),"'$HOME/`id`'");
+        assert_eq!(shell_quote("it's"),"'it'\\''s'");
+        assert_eq!(shell_quote("caf\u{e9}"),"'caf\u{e9}'");
+    }
+}
@@ -0,0 +1,265 @@
+/// Build kubernetes manifests for each node
+use project::node::Node;
+use project::{Project,KubernetesConfig};
+use types::{EnodeAddr,Host,Error};
+use config::{compose,parity,peers};
+use config::peers::Peers;
+use serde_json::{Map,Value};
+use std::path::{Path,PathBuf};
+use toml;
+
+use config::ConfigModule;
+use project::BuildContext;
+
+/// Implementation target for the `ConfigModule` trait.
+pub struct Module;
+
+
+impl ConfigModule for Module {
+
+    fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
+        let project = ctx.project;
+        let kube = match project.kubernetes_config() {
+            Some(kube) => kube,
+            None => return Ok(())
+        };
+        let template: Peers = ctx.project_files.templates().get(peers::FILE_NAME)
+            .unwrap_or(peers::TEMPLATE).parse()?;
+        let network = project.network_config();
+        let bootnodes = network.bootnodes(project.iter_nodes())?;
+        let deployment_node = project.deployment_node().map(|n| n.name());
+        for node in project.iter_nodes().filter(|n| n.internal().is_some()) {
+            let name = node.name();
+            if !is_dns_label(name) {
+                let msg = format!("node name `{}` is not a valid kubernetes resource name",name);
+                return Err(Error::message(msg));
+            }
+            if node.iter_includes().next().is_some() {
+                warn!("includes of `{}` are not carried into its kubernetes manifest",name);
+            }
+            if deployment_node == Some(name) {
+                warn!("deployments of `{}` are not carried into its kubernetes manifest",name);
+            }
+            let files: Vec<(PathBuf,String)> = ctx.build_files.node(name).config().iter()
+                .map(|(path,buff)| (path.to_owned(),buff.to_owned()))
+                .collect();
+            let mut config = Map::new();
+            let mut secrets = Map::new();
+            for (path,buff) in files {
+                if path.starts_with(ACCOUNT_DIR) {
+                    secrets.insert(file_key(&path)?,buff.into());
+                } else if path == Path::new(parity::FILE_NAME) {
+                    let enodes = bootnodes.as_ref().map(|bootnodes| bootnodes.iter()
+                        .filter(|bootnode| bootnode.name() != name)
+                        .flat_map(|bootnode| service_enodes(project,kube,bootnode,&node))
+                        .collect());
+                    // the config holds the node's network key, so is kept with the secrets
+                    let parity_config = with_bootnodes(&buff,enodes)?;
+                    secrets.insert(parity::FILE_NAME.into(),parity_config.into());
+                } else if path == Path::new(peers::FILE_NAME) {
+                    let mut peers = template.clone();
+                    for peer in project.iter_peers(name) {
+                        peers.extend(service_enodes(project,kube,&peer,&node));
+                    }
+                    config.insert(peers::FILE_NAME.into(),peers.to_string().into());
+                } else {
+                    config.insert(file_key(&path)?,buff.into());
+                }
+            }
+            let image = project.image_options(&node).base_image().unwrap_or_else(|| kube.image.clone());
+            let p2p_port = node.network_addr().map(|addr| addr.port()).unwrap_or(30303);
+            let items = vec![
+                service(project,kube,name,p2p_port),
+                config_map(project,kube,name,config),
+                secret(project,kube,name,secrets),
+                stateful_set(project,kube,name,&image,p2p_port)
+            ];
+            let manifest = json!({ "apiVersion": "v1", "kind": "List", "items": items });
+            let path = Path::new(DIR_NAME).join(format!("{}.yml",name));
+            ctx.build_files.project().insert_yaml(path,&manifest)?;
+        }
+        Ok(())
+    }
+}
+
+
+const DIR_NAME: &'static str = "kubernetes";
+
+const ACCOUNT_DIR: &'static str = "accounts";
+
+const CONFIG_MOUNT: &'static str = "/pib-config";
+
+const SECRETS_MOUNT: &'static str = "/pib-secrets";
+
+/// Copies the node's files into place & inserts its keys, as the docker image does at build time.
+const INIT_SCRIPT: &'static str = "set -e
+cp /pib-config/* /pib/
+cp /pib-secrets/config.toml /pib/config.toml
+chmod 600 /pib/config.toml
+mkdir -p /pib/accounts
+cp /pib-secrets/*.env /pib/accounts/
+cd /pib && bash init.sh
+";
+
+
+/// Enodes by which `node` reaches `peer`; internal peers are addressed by their service.
+fn service_enodes(project: &Project, kube: &KubernetesConfig, peer: &Node, node: &Node) -> Vec<EnodeAddr> {
+    match peer.internal() {
+        Some(internal) => {
+            let enode = internal.enode_addr();
+            let host = Host::Domain(kube.service_host(internal.name()));
+            vec![EnodeAddr { host, discport: None, ..enode }]
+        },
+        None => project.reachable_enodes(peer,node)
+    }
+}
+
+
+/// Replace any bootnodes of a generated parity config with their service addresses.
+fn with_bootnodes(buff: &str, bootnodes: Option<Vec<EnodeAddr>>) -> Result<String,Error> {
+    let bootnodes = match bootnodes {
+        Some(bootnodes) => bootnodes,
+        None => return Ok(buff.to_owned()),
+    };
+    let mut config: toml::Value = toml::from_str(buff)?;
+    {
+        let network = config.get_mut("network").and_then(|network| network.as_table_mut())
+            .ok_or_else(|| Error::message("expected `[network]` section in parity config"))?;
+        let bootnodes = bootnodes.iter().map(|enode| enode.to_string().into()).collect();
+        network.insert("bootnodes".into(),toml::Value::Array(bootnodes));
+    }
+    Ok(toml::to_string(&config)?)
+}
+
+
+/// Flatten a config file path into a configmap/secret key.
+fn file_key(path: &Path) -> Result<String,Error> {
+    path.file_name().and_then(|name| name.to_str()).map(String::from).ok_or_else(|| {
+        Error::message(format!("invalid config file path `{}`",path.display()))
+    })
+}
+
+
+/// Check that `name` is a valid RFC 1123 label (as required of service names).
+fn is_dns_label(name: &str) -> bool {
+    name.len() <= 63 && !name.starts_with('-') && !name.ends_with('-') &&
+        name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+
+fn metadata(project: &Project, kube: &KubernetesConfig, name: &str) -> Value {
+    json!({
+        "name": name,
+        "namespace": kube.namespace,
+        "labels": labels(project,name)
+    })
+}
+
+
+fn labels(project: &Project, node: &str) -> Value {
+    json!({ "pib/project": project.project_name(), "pib/node": node })
+}
+
+
+/// Headless service, giving the node a stable DNS name.
+fn service(project: &Project, kube: &KubernetesConfig, name: &str, p2p_port: u16) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Service",
+        "metadata": metadata(project,kube,name),
+        "spec": {
+            "clusterIP": "None",
+            "publishNotReadyAddresses": true,
+            "selector": labels(project,name),
+            "ports": [
+                { "name": "p2p", "port": p2p_port, "protocol": "TCP" },
+                { "name": "p2p-udp", "port": p2p_port, "protocol": "UDP" },
+                { "name": "rpc", "port": compose::RPC_PORT, "protocol": "TCP" },
+                { "name": "ws", "port": compose::WS_PORT, "protocol": "TCP" }
+            ]
+        }
+    })
+}
+
+
+fn config_map(project: &Project, kube: &KubernetesConfig, name: &str, data: Map<String,Value>) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "ConfigMap",
+        "metadata": metadata(project,kube,&format!("{}-config",name)),
+        "data": data
+    })
+}
+
+
+fn secret(project: &Project, kube: &KubernetesConfig, name: &str, data: Map<String,Value>) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Secret",
+        "type": "Opaque",
+        "metadata": metadata(project,kube,&format!("{}-secrets",name)),
+        "stringData": data
+    })
+}
+
+
+fn stateful_set(project: &Project, kube: &KubernetesConfig, name: &str, image: &str, p2p_port: u16) -> Value {
+    let mut claim = json!({
+        "accessModes": ["ReadWriteOnce"],
+        "resources": { "requests": { "storage": kube.storage } }
+    });
+    if let Some(class) = kube.storage_class.as_ref() {
+        claim["storageClassName"] = class.as_str().into();
+    }
+    let config_path = format!("pib/{}",parity::FILE_NAME);
+    json!({
+        "apiVersion": "apps/v1",
+        "kind": "StatefulSet",
+        "metadata": metadata(project,kube,name),
+        "spec": {
+            "serviceName": name,
+            "replicas": 1,
+            "selector": { "matchLabels": labels(project,name) },
+            "template": {
+                "metadata": { "labels": labels(project,name) },
+                "spec": {
+                    "initContainers": [{
+                        "name": "init",
+                        "image": image,
+                        "command": ["/bin/bash","-c",INIT_SCRIPT],
+                        "volumeMounts": [
+                            { "name": "pib", "mountPath": "/pib" },
+                            { "name": "config", "mountPath": CONFIG_MOUNT },
+                            { "name": "secrets", "mountPath": SECRETS_MOUNT }
+                        ]
+                    }],
+                    "containers": [{
+                        "name": "parity",
+                        "image": image,
+                        "workingDir": "/",
+                        "args": ["--config",config_path],
+                        "ports": [
+                            { "name": "p2p", "containerPort": p2p_port, "protocol": "TCP" },
+                            { "name": "p2p-udp", "containerPort": p2p_port, "protocol": "UDP" },
+                            { "name": "rpc", "containerPort": compose::RPC_PORT },
+                            { "name": "ws", "containerPort": compose::WS_PORT }
+                        ],
+                        "volumeMounts": [
+                            { "name": "pib", "mountPath": "/pib" },
+                            { "name": "state", "mountPath": "/pib/state" }
+                        ]
+                    }],
+                    "volumes": [
+                        { "name": "pib", "emptyDir": {} },
+                        { "name": "config", "configMap": { "name": format!("{}-config",name) } },
+                        { "name": "secrets", "secret": { "secretName": format!("{}-secrets",name) } }
+                    ]
+                }
+            },
+            "volumeClaimTemplates": [{
+                "metadata": { "name": "state" },
+                "spec": claim
+            }]
+        }
+    })
+}
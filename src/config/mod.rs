@@ -7,6 +7,9 @@ pub mod scripts;
 pub mod parity;
 pub mod chain;
 pub mod peers; 
+pub mod deploy;
+pub mod kubernetes;
+pub mod host;
 
 
 pub mod vars {
@@ -28,7 +31,8 @@ pub mod vars {
         fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
             let mut shared_vars = Vars::default();
             shared_vars.insert("PROJECT_NAME",ctx.project.project_name());
-            for contract in ctx.project.iter_contracts() {
+            let deployed = ctx.project.iter_deployments().map(|d| &d.contract);
+            for contract in ctx.project.iter_contracts().chain(deployed) {
                 let key = format!("CONTRACT_{}",contract.name);
                 let val = util::hex_string(&contract.addr);
                 shared_vars.insert(&key,val);
@@ -45,6 +49,10 @@ pub mod vars {
                 let network_addr = node.network_addr();
                 node_vars.insert("NETWORK_HOST",network_addr.ip().to_string());
                 node_vars.insert("NETWORK_PORT",network_addr.port().to_string());
+                if let Some(public_addr) = node.public_addr() {
+                    node_vars.insert("PUBLIC_HOST",public_addr.ip().to_string());
+                    node_vars.insert("PUBLIC_PORT",public_addr.port().to_string());
+                }
 
                 ctx.build_files.node(node.name()).config().insert(NODE_VARS_FILENAME,node_vars.to_string());
 
@@ -131,6 +139,8 @@ pub mod vars {
 pub mod docker {
     use types::Error;
     use config::ConfigModule;
+    use config::deploy;
+    use project::node::Node;
     use project::{SetupContext,BuildContext};
 
     /// Implementation target for the `ConfigModule` trait.
@@ -155,17 +165,98 @@ pub mod docker {
                 dockerignore.push_str(pattern.trim());
                 dockerignore.push('\n');
             }
+            if let Some(docker) = ctx.project.docker_config() {
+                for name in docker.node.keys() {
+                    if ctx.project.iter_nodes().filter_map(|n| n.internal()).all(|node| node.name() != name) {
+                        let msg = format!("image settings given for unknown node `{}`",name);
+                        return Err(Error::message(msg));
+                    }
+                }
+            }
+            let deployment_node = ctx.project.deployment_node().map(|n| n.name());
             for node in ctx.project.iter_nodes().filter_map(|n| n.internal()) {
-                ctx.build_files.node(node.name()).root().insert(FILE_NAME,dockerfile);
+                let node_dockerfile = match ctx.project.image_options(&Node::from(node)).base_image() {
+                    Some(image) => {
+                        info!("using base image `{}` for `{}`",image,node.name());
+                        with_base_image(dockerfile,&image)?
+                    },
+                    None => dockerfile.to_owned(),
+                };
+                if deployment_node == Some(node.name()) {
+                    let dockerfile = format!("{}\n{}\n",node_dockerfile.trim_right(),deploy::ENTRYPOINT);
+                    ctx.build_files.node(node.name()).root().insert(FILE_NAME,dockerfile);
+                } else {
+                    ctx.build_files.node(node.name()).root().insert(FILE_NAME,node_dockerfile);
+                }
                 ctx.build_files.node(node.name()).root().insert(".dockerignore",dockerignore.clone());
             }
             Ok(())
         }
     }
 
+    /// Replace the base image of a Dockerfile (i.e. the image of its first `FROM` instruction).
+    fn with_base_image(dockerfile: &str, image: &str) -> Result<String,Error> {
+        let mut replaced = false;
+        let mut buff = String::new();
+        for line in dockerfile.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some(instruction) if !replaced && instruction.eq_ignore_ascii_case("FROM") => {
+                    // retain any flags (e.g. `--platform=...`) & stage name, replacing only the image
+                    let words: Vec<&str> = words.collect();
+                    let flags: Vec<&str> = words.iter().cloned().take_while(|word| word.starts_with("--")).collect();
+                    let rest: Vec<&str> = words.iter().cloned().skip(flags.len() + 1).collect();
+                    buff.push_str("FROM ");
+                    if !flags.is_empty() {
+                        buff.push_str(&flags.join(" "));
+                        buff.push(' ');
+                    }
+                    buff.push_str(image);
+                    if !rest.is_empty() {
+                        buff.push(' ');
+                        buff.push_str(&rest.join(" "));
+                    }
+                    replaced = true;
+                },
+                _ => buff.push_str(line),
+            }
+            buff.push('\n');
+        }
+        if replaced {
+            Ok(buff)
+        } else {
+            Err(Error::message("expected a `FROM` instruction in the Dockerfile template"))
+        }
+    }
+
     pub const FILE_NAME: &'static str = "Dockerfile";
 
     pub const TEMPLATE: &'static str = include_str!("../include/Dockerfile");
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::{with_base_image,TEMPLATE};
+
+        #[test]
+        fn replaces_template_image() {
+            let dockerfile = with_base_image(TEMPLATE,"mimirbridge/pib-parity:2.0.8").unwrap();
+            assert!(dockerfile.starts_with("FROM mimirbridge/pib-parity:2.0.8\n"));
+            assert_eq!(dockerfile.lines().count(),TEMPLATE.lines().count());
+        }
+
+        #[test]
+        fn keeps_flags_and_stage_name() {
+            let dockerfile = "from --platform=linux/amd64 parity/parity:v2.0.8 AS node\nFROM node\n";
+            let replaced = with_base_image(dockerfile,"example/parity:2.1").unwrap();
+            assert_eq!(replaced,"FROM --platform=linux/amd64 example/parity:2.1 AS node\nFROM node\n");
+        }
+
+        #[test]
+        fn requires_from_instruction() {
+            assert!(with_base_image("WORKDIR /\n","example/parity:2.1").is_err());
+        }
+    }
 }
 
 
@@ -178,7 +269,9 @@ pub const TEMPLATES: &[(&str,&str)] = &[
     (parity::AUTHORITY_FILENAME,parity::AUTHORITY_TEMPLATE),
     (parity::INTERFACE_FILENAME,parity::INTERFACE_TEMPLATE),
     (docker::FILE_NAME,docker::TEMPLATE),
-    (scripts::INIT_FILENAME,scripts::INIT_TEMPLATE)
+    (scripts::INIT_FILENAME,scripts::INIT_TEMPLATE),
+    (deploy::SCRIPT_FILENAME,deploy::SCRIPT_TEMPLATE),
+    (host::INSTALL_FILENAME,host::INSTALL_TEMPLATE),
 ];
 
 
@@ -200,7 +293,9 @@ pub trait ConfigModule {
 }
 
 
-/// All `ConfigModule` implementers in no particular order
+/// All `ConfigModule` implementers, in build order.  Most modules are independent of one
+/// another, but `kubernetes` & `host` repackage the node files generated by the others, and
+/// so must remain last.
 pub const MODULES: &[&dyn ConfigModule] = &[
     &include::Module,
     &chain::Module,
@@ -209,8 +304,11 @@ pub const MODULES: &[&dyn ConfigModule] = &[
     &parity::Module,
     &peers::Module,
     &scripts::Module,
+    &deploy::Module,
     &docker::Module,
     &vars::Module,
+    &kubernetes::Module,
+    &host::Module,
 ];
 
 
@@ -1,10 +1,13 @@
 /// Parse and build parity's main `config.toml` file
 use mimir_crypto::secp256k1::{Address,Secret};
-use project::node::NodeRole;
-use types::Error;
+use project::node::{Node,NodeRole};
+use project::network::Discovery;
+use types::{EnodeAddr,Error};
 use util;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::str::FromStr;
+use semver::Version;
 use toml::value::Value;
 use toml;
 
@@ -29,6 +32,12 @@ impl ConfigModule for Module {
             .unwrap_or(AUTHORITY_TEMPLATE).parse()?;
         let interface_template: ParityConfig = ctx.project_files.templates().get(INTERFACE_FILENAME)
             .unwrap_or(INTERFACE_TEMPLATE).parse()?;
+        let network = ctx.project.network_config();
+        let bootnodes = network.bootnodes(ctx.project.iter_nodes())?;
+        let publish_authorities = ctx.project.compose_config()
+            .and_then(|compose| compose.ports.as_ref())
+            .map(|ports| ports.authorities)
+            .unwrap_or(false);
         for node in ctx.project.iter_nodes().filter_map(|n| n.internal()) {
             let mut config = match node.node_role() {
                 NodeRole::Authority => authority_template.clone(),
@@ -36,6 +45,31 @@ impl ConfigModule for Module {
             };
             config.set_network_key(node.network_key());
             config.set_account_addr(node.account_addr());
+            if let (true,NodeRole::Authority) = (publish_authorities,node.node_role()) {
+                // published ports are unreachable if the servers only listen on localhost
+                config.listen_all("rpc");
+                config.listen_all("websockets");
+            }
+            if let Some(public_addr) = node.public_addr() {
+                if public_addr.port() != node.network_addr().port() {
+                    warn!("parity advertises `{}` on its network port ({}), not the public port ({})",
+                        node.name(),node.network_addr().port(),public_addr.port());
+                }
+                config.set_nat_addr(public_addr);
+            }
+            if let Some(bootnodes) = bootnodes.as_ref() {
+                let this = Node::from(node);
+                let enodes = bootnodes.iter()
+                    .filter(|bootnode| bootnode.name() != node.name())
+                    .flat_map(|bootnode| ctx.project.reachable_enodes(bootnode,&this))
+                    .collect();
+                config.set_bootnodes(enodes);
+            } else if network.discovery == Discovery::ReservedOnly {
+                config.set_reserved_only();
+            }
+            if let Some(version) = ctx.project.image_options(&Node::from(node)).parity_version() {
+                config.adjust_for(node.name(),&version);
+            }
             ctx.build_files.node(node.name()).config().insert_toml(FILE_NAME,&config)?;
         }
         Ok(())
@@ -55,6 +89,43 @@ pub const AUTHORITY_TEMPLATE: &'static str = include_str!("../include/authority-
 pub const INTERFACE_TEMPLATE: &'static str = include_str!("../include/interface-config.toml");
 
 
+/// A config option (or entire section) known only to a range of parity versions
+struct VersionedOption {
+    section: &'static str,
+    key: Option<&'static str>,
+    /// First version (major, minor) which knows the option
+    since: Option<(u64,u64)>,
+    /// First version (major, minor) which no longer knows the option
+    until: Option<(u64,u64)>,
+}
+
+
+impl VersionedOption {
+
+    fn is_known(&self, version: &Version) -> bool {
+        let version = (version.major,version.minor);
+        self.since.map(|since| version >= since).unwrap_or(true) &&
+            self.until.map(|until| version < until).unwrap_or(true)
+    }
+}
+
+
+/// Options of the default templates which are unknown to some parity versions, and are dropped
+/// by `adjust_for`.  No table of renamed options is kept, so custom templates must use the
+/// option names which their parity version accepts.
+const VERSIONED_OPTIONS: &[VersionedOption] = &[
+    // websockets server added in 1.8
+    VersionedOption { section: "websockets", key: None, since: Some((1,8)), until: None },
+    // ui & dapps removed in 2.0
+    VersionedOption { section: "ui", key: None, since: None, until: Some((2,0)) },
+    VersionedOption { section: "dapps", key: None, since: None, until: Some((2,0)) },
+    // updater removed in 3.0
+    VersionedOption { section: "parity", key: Some("no_download"), since: None, until: Some((3,0)) },
+    // secret store removed in 3.1
+    VersionedOption { section: "secretstore", key: None, since: None, until: Some((3,1)) },
+];
+
+
 /// Parity config file
 #[derive(Debug,Clone,Serialize,Deserialize)]
 pub struct ParityConfig {
@@ -78,6 +149,50 @@ impl ParityConfig {
         self.network.insert("node_key".into(),util::hex_string(key.as_ref()).into());
     }
 
+    /// Advertise `addr` as the node's external address (e.g. when behind NAT).  Parity's `nat`
+    /// setting carries only the ip, so the advertised port remains the listening port.
+    pub fn set_nat_addr(&mut self, addr: SocketAddr) {
+        self.network.insert("nat".into(),format!("extip:{}",addr.ip()).into());
+    }
+
+    /// Listen on all interfaces for the given server section (unless configured otherwise).
+    pub fn listen_all(&mut self, section: &str) {
+        let table = self.ext.entry(section.to_owned())
+            .or_insert_with(|| Value::Table(Default::default()));
+        if let Value::Table(table) = table {
+            table.entry("interface".to_owned()).or_insert_with(|| "0.0.0.0".into());
+        }
+    }
+
+    /// Discover peers via `bootnodes` rather than reserved peers.
+    pub fn set_bootnodes(&mut self, bootnodes: Vec<EnodeAddr>) {
+        let bootnodes = bootnodes.iter().map(|enode| enode.to_string().into()).collect();
+        self.network.remove("reserved_peers");
+        self.network.insert("bootnodes".into(),Value::Array(bootnodes));
+    }
+
+    pub fn set_reserved_only(&mut self) {
+        self.network.insert("reserved_only".into(),true.into());
+    }
+
+    /// Drop any options which are unknown to the given parity version.
+    pub fn adjust_for(&mut self, node: &str, version: &Version) {
+        for option in VERSIONED_OPTIONS.iter().filter(|option| !option.is_known(version)) {
+            let removed = match option.key {
+                None => self.ext.remove(option.section).is_some(),
+                Some(key) => match self.ext.get_mut(option.section) {
+                    Some(Value::Table(table)) => table.remove(key).is_some(),
+                    _ => false,
+                },
+            };
+            if removed {
+                let name = option.key.map(|key| format!("{}.{}",option.section,key))
+                    .unwrap_or_else(|| option.section.to_owned());
+                debug!("dropping `{}` from config of `{}` (unknown to parity {})",name,node,version);
+            }
+        }
+    }
+
     pub fn set_account_addr(&mut self, addr: Address) {
         self.mining.insert("engine_signer".into(),addr.to_string().into());
     }
@@ -94,3 +209,54 @@ impl FromStr for ParityConfig {
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use super::{ParityConfig,AUTHORITY_TEMPLATE};
+
+    fn adjusted(version: &str) -> ParityConfig {
+        let mut config: ParityConfig = AUTHORITY_TEMPLATE.parse().unwrap();
+        config.adjust_for("node-0",&Version::parse(version).unwrap());
+        config
+    }
+
+    fn has_option(config: &ParityConfig, section: &str, key: &str) -> bool {
+        config.ext.get(section).and_then(|section| section.get(key)).is_some()
+    }
+
+    #[test]
+    fn drops_websockets_before_1_8() {
+        let config = adjusted("1.7.13");
+        assert!(!config.ext.contains_key("websockets"));
+        assert!(config.ext.contains_key("ui"));
+        assert!(config.ext.contains_key("rpc"));
+    }
+
+    #[test]
+    fn drops_ui_from_2_0() {
+        let config = adjusted("2.0.8");
+        assert!(config.ext.contains_key("websockets"));
+        assert!(!config.ext.contains_key("ui"));
+        assert!(has_option(&config,"parity","no_download"));
+        assert!(config.ext.contains_key("secretstore"));
+    }
+
+    #[test]
+    fn drops_updater_and_secret_store() {
+        let config = adjusted("3.1.0");
+        assert!(!has_option(&config,"parity","no_download"));
+        assert!(has_option(&config,"parity","chain"));
+        assert!(!config.ext.contains_key("secretstore"));
+    }
+
+    #[test]
+    fn keeps_options_of_current_versions() {
+        let config = adjusted("1.11.11");
+        for section in ["ui","rpc","websockets","ipc","secretstore","parity"].iter() {
+            assert!(config.ext.contains_key(*section),"missing `[{}]`",section);
+        }
+        assert!(has_option(&config,"parity","no_download"));
+    }
+}
@@ -1,4 +1,4 @@
-/// Build `peers.txt` file for authorities
+/// Build `peers.txt` file for each node
 use types::{EnodeAddr,Error};
 use std::str::FromStr;
 use std::fmt;
@@ -14,21 +14,26 @@ pub struct Module;
 impl ConfigModule for Module {
 
     fn build(&self, ctx: &mut BuildContext) -> Result<(),Error> {
-        let mut peers: Peers = ctx.project_files.templates().get(FILE_NAME)
+        if !ctx.project.network_config().uses_reserved_peers() {
+            return Ok(());
+        }
+        let template: Peers = ctx.project_files.templates().get(FILE_NAME)
             .unwrap_or(TEMPLATE).parse()?;
-        peers.extend(ctx.project.iter_nodes().map(|n| n.enode_addr()));
-        let peer_buff = peers.to_string();
         for node in ctx.project.iter_nodes() {
-            ctx.build_files.node(node.name()).config().insert(FILE_NAME,peer_buff.clone());
+            let mut peers = template.clone();
+            for peer in ctx.project.iter_peers(node.name()) {
+                peers.extend(ctx.project.reachable_enodes(&peer,&node));
+            }
+            ctx.build_files.node(node.name()).config().insert(FILE_NAME,peers.to_string());
         }
         Ok(())
     }
 }
 
 
-const FILE_NAME: &'static str = "peers.txt";
+pub const FILE_NAME: &'static str = "peers.txt";
 
-const TEMPLATE: &'static str = "";
+pub const TEMPLATE: &'static str = "";
 
 
 #[derive(Default,Debug,Clone)]
@@ -0,0 +1,201 @@
+//! Embedded EVM for offline execution of genesis constructors.
+use mimir_crypto::secp256k1::Address;
+use mimir_types::Bytes;
+use project::storage::Storage;
+use types::Error;
+use revm::{Evm,InMemoryDB};
+use revm::primitives::{self,AccountInfo,Bytecode,ExecutionResult,SpecId,TxKind,U256};
+use ethabi::{self,ParamType,Token};
+
+
+/// Intrinsic gas cost of the transaction used to invoke a constructor
+const INTRINSIC_GAS: u64 = 21000;
+
+/// Selector of `Error(string)`, used by solidity to encode revert reasons
+const ERROR_SELECTOR: [u8;4] = [0x08,0xc3,0x79,0xa0];
+
+
+/// Outcome of a successful constructor execution
+#[derive(Debug,Copy,Clone)]
+pub struct ConstructorReport {
+    /// Gas consumed by the constructor
+    pub gas_used: u64,
+    /// Size of the resulting runtime code
+    pub code_size: usize,
+}
+
+
+/// Genesis state against which constructors are executed.
+///
+/// Constructors run with the code of the account set to the constructor code,
+/// mirroring the way in which parity deploys genesis contracts (i.e. `address(this)`
+/// is the genesis address of the contract, and `msg.sender` is the zero address).
+///
+/// Constructors run under byzantium rules (`REVERT`, `RETURNDATA*` & `STATICCALL`) only
+/// if the chain enables them at genesis; otherwise those opcodes are invalid, as in parity.
+///
+pub struct DryRun {
+    db: InMemoryDB,
+    gas_limit: u64,
+    spec_id: SpecId,
+}
+
+
+impl DryRun {
+
+    pub fn new(gas_limit: u64, byzantium: bool) -> Self {
+        let spec_id = if byzantium { SpecId::BYZANTIUM } else { SpecId::SPURIOUS_DRAGON };
+        Self { db: InMemoryDB::default(), gas_limit, spec_id }
+    }
+
+    /// Insert a plain (non-contract) genesis account.
+    pub fn insert_account(&mut self, addr: Address, balance: u64) {
+        let info = AccountInfo { balance: U256::from(balance), ..Default::default() };
+        self.db.insert_account_info(to_address(addr),info);
+    }
+
+    /// Insert a contract in its initialized state (runtime code & storage).
+    pub fn insert_initialized(&mut self, addr: Address, balance: u64, runtime: &Bytes, storage: &Storage) {
+        let address = to_address(addr);
+        let code = Bytecode::new_raw(runtime.clone().into_inner().into());
+        let info = AccountInfo { balance: U256::from(balance), code: Some(code), ..Default::default() };
+        self.db.insert_account_info(address,info);
+        for (slot,value) in storage.iter() {
+            let (slot,value) = (U256::from_be_bytes(*slot),U256::from_be_bytes(*value));
+            let _ = self.db.insert_account_storage(address,slot,value);
+        }
+    }
+
+    /// Execute a constructor, storing the resulting code & storage at `addr`.
+    pub fn run_constructor(&mut self, name: &str, addr: Address, balance: u64, code: &Bytes) -> Result<ConstructorReport,Error> {
+        let address = to_address(addr);
+        let init_code = Bytecode::new_raw(code.clone().into_inner().into());
+        let info = AccountInfo { balance: U256::from(balance), code: Some(init_code), ..Default::default() };
+        self.db.insert_account_info(address,info);
+        let (gas_limit,spec_id) = (self.gas_limit,self.spec_id);
+        let result = {
+            let mut evm = Evm::builder()
+                .with_db(&mut self.db)
+                .with_spec_id(spec_id)
+                .modify_tx_env(|tx| {
+                    tx.caller = primitives::Address::ZERO;
+                    tx.transact_to = TxKind::Call(address);
+                    tx.gas_limit = gas_limit;
+                    tx.gas_price = U256::ZERO;
+                })
+                .build();
+            evm.transact_commit().map_err(|err| {
+                Error::message(format!("unable to execute constructor of `{}`: {:?}",name,err))
+            })?
+        };
+        match result {
+            ExecutionResult::Success { gas_used, output, .. } => {
+                let runtime = output.into_data();
+                let code_size = runtime.len();
+                let mut info = self.db.load_account(address)?.info.clone();
+                info.code = Some(Bytecode::new_raw(runtime));
+                info.code_hash = primitives::KECCAK_EMPTY;
+                self.db.insert_account_info(address,info);
+                let gas_used = gas_used.saturating_sub(INTRINSIC_GAS);
+                Ok(ConstructorReport { gas_used, code_size })
+            },
+            ExecutionResult::Revert { output, .. } => {
+                let msg = match revert_reason(&output) {
+                    Some(reason) => format!("constructor of `{}` reverted: {}",name,reason),
+                    None => format!("constructor of `{}` reverted",name),
+                };
+                Err(Error::message(msg))
+            },
+            ExecutionResult::Halt { reason, gas_used } => {
+                let msg = format!("constructor of `{}` halted after using {} gas ({:?})",name,gas_used,reason);
+                Err(Error::message(msg))
+            },
+        }
+    }
+}
+
+
+fn to_address(addr: Address) -> primitives::Address {
+    primitives::Address::new(addr.into_inner())
+}
+
+
+/// Decode the message of a solidity `revert("...")` if present.
+fn revert_reason(output: &[u8]) -> Option<String> {
+    if output.len() > 4 && output[..4] == ERROR_SELECTOR {
+        match ethabi::decode(&[ParamType::String],&output[4..]).ok()?.pop()? {
+            Token::String(reason) => Some(reason),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::Address;
+    use mimir_types::Bytes;
+    use ethabi::{self,Token};
+    use super::*;
+
+    const GAS_LIMIT: u64 = 1_000_000;
+
+    /// Returns the single byte `0x01` as runtime code.
+    const RETURNS_CODE: &[u8] = &[0x60,0x01,0x60,0x00,0x52,0x60,0x01,0x60,0x1f,0xf3];
+
+    /// `revert(0,0)`
+    const REVERTS: &[u8] = &[0x60,0x00,0x60,0x00,0xfd];
+
+    /// Jumps back to its first instruction forever.
+    const LOOPS: &[u8] = &[0x5b,0x60,0x00,0x56];
+
+    fn address() -> Address {
+        "00000000000000000000000000000000000000aa".parse().unwrap()
+    }
+
+    fn run(byzantium: bool, code: &[u8]) -> Result<ConstructorReport,Error> {
+        let mut dry_run = DryRun::new(GAS_LIMIT,byzantium);
+        dry_run.run_constructor("test",address(),0,&Bytes::from(code.to_vec()))
+    }
+
+    fn error_message(result: Result<ConstructorReport,Error>) -> String {
+        result.err().expect("constructor must fail").to_string()
+    }
+
+    #[test]
+    fn successful_constructor() {
+        let report = run(true,RETURNS_CODE).unwrap();
+        assert_eq!(report.code_size,1);
+        assert!(report.gas_used > 0 && report.gas_used < GAS_LIMIT);
+    }
+
+    #[test]
+    fn reverting_constructor() {
+        let msg = error_message(run(true,REVERTS));
+        assert!(msg.contains("reverted"),"unexpected error: {}",msg);
+    }
+
+    #[test]
+    fn revert_is_invalid_before_byzantium() {
+        let msg = error_message(run(false,REVERTS));
+        assert!(msg.contains("halted"),"unexpected error: {}",msg);
+    }
+
+    #[test]
+    fn out_of_gas_constructor() {
+        let msg = error_message(run(true,LOOPS));
+        assert!(msg.contains("halted after using"),"unexpected error: {}",msg);
+        assert!(msg.contains("OutOfGas"),"unexpected error: {}",msg);
+    }
+
+    #[test]
+    fn revert_reasons() {
+        let mut output = ERROR_SELECTOR.to_vec();
+        output.extend(ethabi::encode(&[Token::String("not allowed".into())]));
+        assert_eq!(revert_reason(&output),Some("not allowed".to_owned()));
+        assert_eq!(revert_reason(&[]),None);
+        assert_eq!(revert_reason(&ERROR_SELECTOR),None);
+    }
+}
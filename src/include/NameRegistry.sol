@@ -0,0 +1,22 @@
+pragma solidity ^0.4.24;
+
+
+/// Read-only map between the names of project entities (nodes, actors &
+/// contracts) and their addresses.  Storage is populated by pib at genesis,
+/// so the layout of the state variables below must not change.
+contract NameRegistry {
+
+    /// Address registered under each name (slot 0)
+    mapping(string => address) addresses;
+
+    /// Name registered for each address (slot 1)
+    mapping(address => string) names;
+
+    function lookup(string _name) public view returns (address) {
+        return addresses[_name];
+    }
+
+    function nameOf(address _addr) public view returns (string) {
+        return names[_addr];
+    }
+}
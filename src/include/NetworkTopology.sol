@@ -0,0 +1,73 @@
+pragma solidity ^0.4.24;
+
+
+/// Record of the network's nodes & their reserved peers.  Storage is populated
+/// by pib at genesis, so the layout of the state variables below must not change.
+contract NetworkTopology {
+
+    event NodeAdded(uint256 indexed _index, address indexed _account);
+
+    event NodeUpdated(uint256 indexed _index);
+
+    struct Node {
+        string name;
+        /// 0 for authorities, 1 for interfaces
+        uint8 role;
+        string enode;
+        address account;
+    }
+
+    /// All nodes, in order of declaration (slot 0)
+    Node[] public nodes;
+
+    /// One plus the index of each node, by account address (slot 1)
+    mapping(address => uint256) indexOf;
+
+    /// Indices of the reserved peers of each node (slot 2)
+    mapping(uint256 => uint256[]) peers;
+
+    /// Accounts which may modify the topology (slot 3)
+    mapping(address => bool) public admins;
+
+    modifier onlyAdmin() {
+        require(admins[msg.sender]);
+        _;
+    }
+
+    function nodeCount() public view returns (uint256) {
+        return nodes.length;
+    }
+
+    function peersOf(uint256 _index) public view returns (uint256[]) {
+        require(_index < nodes.length);
+        return peers[_index];
+    }
+
+    function lookup(address _account) public view returns (bool, uint256) {
+        uint256 index = indexOf[_account];
+        return index == 0 ? (false, 0) : (true, index - 1);
+    }
+
+    function addNode(string _name, uint8 _role, string _enode, address _account) public onlyAdmin returns (uint256) {
+        require(indexOf[_account] == 0);
+        uint256 index = nodes.push(Node(_name, _role, _enode, _account)) - 1;
+        indexOf[_account] = index + 1;
+        emit NodeAdded(index, _account);
+        return index;
+    }
+
+    function setEnode(uint256 _index, string _enode) public onlyAdmin {
+        require(_index < nodes.length);
+        nodes[_index].enode = _enode;
+        emit NodeUpdated(_index);
+    }
+
+    function setPeers(uint256 _index, uint256[] _peers) public onlyAdmin {
+        require(_index < nodes.length);
+        for (uint256 i = 0; i < _peers.length; i++) {
+            require(_peers[i] < nodes.length);
+        }
+        peers[_index] = _peers;
+        emit NodeUpdated(_index);
+    }
+}
@@ -0,0 +1,58 @@
+pragma solidity ^0.4.24;
+
+
+/// Transaction permissioning, as expected by parity's `transactionPermissionContract`.
+/// Storage is populated by pib at genesis, so the layout of the state variables
+/// below must not change.
+contract TxPermission {
+
+    uint32 constant ALL = 0xffffffff;
+
+    uint32 constant NONE = 0;
+
+    /// Senders permitted to transact (slot 0)
+    mapping(address => bool) public allowed;
+
+    /// Accounts which may modify the allow-list (slot 1)
+    mapping(address => bool) public admins;
+
+    modifier onlyAdmin() {
+        require(admins[msg.sender]);
+        _;
+    }
+
+    function allowedTxTypes(address _sender) public view returns (uint32) {
+        return allowed[_sender] ? ALL : NONE;
+    }
+
+    function setAllowed(address _sender, bool _allowed) public onlyAdmin {
+        allowed[_sender] = _allowed;
+    }
+}
+
+
+/// Node permissioning, as expected by parity's `nodePermissionContract`.  Nodes are
+/// identified by the hash of their 64-byte public key, split into low & high halves.
+/// Storage is populated by pib at genesis, so the layout of the state variables
+/// below must not change.
+contract NodePermission {
+
+    /// Permitted nodes (slot 0)
+    mapping(bytes32 => bool) public allowed;
+
+    /// Accounts which may modify the allow-list (slot 1)
+    mapping(address => bool) public admins;
+
+    modifier onlyAdmin() {
+        require(admins[msg.sender]);
+        _;
+    }
+
+    function connectionAllowed(bytes32 _sl, bytes32 _sh, bytes32 _pl, bytes32 _ph) public view returns (bool) {
+        return allowed[keccak256(abi.encodePacked(_sl, _sh))] && allowed[keccak256(abi.encodePacked(_pl, _ph))];
+    }
+
+    function setAllowed(bytes32 _low, bytes32 _high, bool _allowed) public onlyAdmin {
+        allowed[keccak256(abi.encodePacked(_low, _high))] = _allowed;
+    }
+}
@@ -0,0 +1,63 @@
+pragma solidity ^0.4.24;
+
+
+/// Minimal ERC-20 token.  Balances & metadata are populated by pib at genesis,
+/// so the layout of the state variables below must not change.
+contract StandardToken {
+
+    event Transfer(address indexed _from, address indexed _to, uint256 _value);
+
+    event Approval(address indexed _owner, address indexed _spender, uint256 _value);
+
+    /// Balance of each account (slot 0)
+    mapping(address => uint256) balances;
+
+    /// Allowance of each spender, by owner (slot 1)
+    mapping(address => mapping(address => uint256)) allowed;
+
+    /// Sum of all balances (slot 2)
+    uint256 public totalSupply;
+
+    /// Token name (slot 3)
+    string public name;
+
+    /// Token symbol (slot 4)
+    string public symbol;
+
+    /// Number of decimals (slot 5)
+    uint8 public decimals;
+
+    function balanceOf(address _owner) public view returns (uint256) {
+        return balances[_owner];
+    }
+
+    function allowance(address _owner, address _spender) public view returns (uint256) {
+        return allowed[_owner][_spender];
+    }
+
+    function transfer(address _to, uint256 _value) public returns (bool) {
+        require(_to != address(0));
+        require(_value <= balances[msg.sender]);
+        balances[msg.sender] -= _value;
+        balances[_to] += _value;
+        emit Transfer(msg.sender, _to, _value);
+        return true;
+    }
+
+    function transferFrom(address _from, address _to, uint256 _value) public returns (bool) {
+        require(_to != address(0));
+        require(_value <= balances[_from]);
+        require(_value <= allowed[_from][msg.sender]);
+        balances[_from] -= _value;
+        allowed[_from][msg.sender] -= _value;
+        balances[_to] += _value;
+        emit Transfer(_from, _to, _value);
+        return true;
+    }
+
+    function approve(address _spender, uint256 _value) public returns (bool) {
+        allowed[msg.sender][_spender] = _value;
+        emit Approval(msg.sender, _spender, _value);
+        return true;
+    }
+}
@@ -0,0 +1,126 @@
+pragma solidity ^0.4.24;
+
+
+/// Interface expected by parity's `safeContract` validator engine option.
+contract ValidatorSet {
+
+    /// Issued when the validator set changes; parity calls `finalizeChange`
+    /// once the change has been finalized by consensus.
+    event InitiateChange(bytes32 indexed _parentHash, address[] _newSet);
+
+    function getValidators() public view returns (address[]);
+
+    function finalizeChange() public;
+}
+
+
+/// Non-reporting validator set in which any current validator may add or
+/// remove validators.  Suitable for `safeContract`.
+contract SimpleValidatorSet is ValidatorSet {
+
+    event ChangeFinalized(address[] _currentSet);
+
+    address constant SYSTEM_ADDRESS = 0xfffffffffffffffffffffffffffffffffffffffe;
+
+    address[] validators;
+
+    address[] pending;
+
+    mapping(address => bool) isPending;
+
+    bool finalized;
+
+    modifier onlySystem() {
+        require(msg.sender == SYSTEM_ADDRESS);
+        _;
+    }
+
+    modifier onlyValidator() {
+        require(isValidator(msg.sender));
+        _;
+    }
+
+    modifier whenFinalized() {
+        require(finalized);
+        _;
+    }
+
+    constructor(address[] _initial) public {
+        require(_initial.length > 0);
+        for (uint i = 0; i < _initial.length; i++) {
+            require(!isPending[_initial[i]]);
+            isPending[_initial[i]] = true;
+        }
+        validators = _initial;
+        pending = _initial;
+        finalized = true;
+    }
+
+    function getValidators() public view returns (address[]) {
+        return validators;
+    }
+
+    function getPending() public view returns (address[]) {
+        return pending;
+    }
+
+    function isValidator(address _validator) public view returns (bool) {
+        for (uint i = 0; i < validators.length; i++) {
+            if (validators[i] == _validator) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    function finalizeChange() public onlySystem {
+        validators = pending;
+        finalized = true;
+        emit ChangeFinalized(validators);
+    }
+
+    function addValidator(address _validator) public onlyValidator whenFinalized {
+        require(!isPending[_validator]);
+        isPending[_validator] = true;
+        pending.push(_validator);
+        initiateChange();
+    }
+
+    function removeValidator(address _validator) public onlyValidator whenFinalized {
+        require(isPending[_validator]);
+        require(pending.length > 1);
+        for (uint i = 0; i < pending.length; i++) {
+            if (pending[i] == _validator) {
+                pending[i] = pending[pending.length - 1];
+                pending.length--;
+                break;
+            }
+        }
+        isPending[_validator] = false;
+        initiateChange();
+    }
+
+    function initiateChange() internal {
+        finalized = false;
+        emit InitiateChange(blockhash(block.number - 1), pending);
+    }
+}
+
+
+/// Validator set which additionally accepts misbehaviour reports from parity.
+/// Suitable for `contract`.  Reports are only logged; acting on them is left
+/// to the validators (e.g. via `removeValidator`).
+contract ReportingValidatorSet is SimpleValidatorSet {
+
+    event Report(address indexed _reporter, address indexed _reported, uint256 indexed _blockNumber, bool _malicious);
+
+    constructor(address[] _initial) SimpleValidatorSet(_initial) public { }
+
+    function reportBenign(address _validator, uint256 _blockNumber) public onlyValidator {
+        emit Report(msg.sender, _validator, _blockNumber, false);
+    }
+
+    function reportMalicious(address _validator, uint256 _blockNumber, bytes /* _proof */) public onlyValidator {
+        emit Report(msg.sender, _validator, _blockNumber, true);
+    }
+}
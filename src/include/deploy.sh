@@ -0,0 +1,62 @@
+#!/bin/bash
+
+# Submits the pre-signed deployment transactions in `deployments.txt` to the
+# local node on first start, then runs parity with the supplied arguments.
+
+set -e
+
+RPC_URL=${PIB_RPC_URL:-"http://127.0.0.1:8545"}
+
+PARITY_BIN=${PIB_PARITY_BIN:-"parity"}
+
+TX_FILE="/pib/deployments.txt"
+
+MARKER="/pib/state/deployed"
+
+FAILED="/pib/state/deploy-failed"
+
+rpc() {
+    curl -sf -H "Content-Type: application/json" --data "$1" "$RPC_URL"
+}
+
+# record the failure & stop parity (`$$` is parity once it has been exec'd), so that a failed
+# deployment stops the container rather than going unnoticed; it is retried on next start.
+fail() {
+    echo "pib: $1" | tee "$FAILED" >&2
+    kill "$$"
+    exit 1
+}
+
+replay() {
+    # wait for the RPC interface to come up...
+    until rpc '{"jsonrpc":"2.0","method":"net_version","params":[],"id":0}' > /dev/null
+    do
+        sleep 1
+    done
+
+    while read -r label raw
+    do
+        echo "pib: submitting $label"
+        if ! reply=$(rpc "{\"jsonrpc\":\"2.0\",\"method\":\"eth_sendRawTransaction\",\"params\":[\"$raw\"],\"id\":1}"); then
+            fail "failed to submit $label"
+        fi
+        echo "$reply"
+        # a retry after a partial failure resubmits transactions which were already accepted
+        if echo "$reply" | grep -q "already imported"; then
+            continue
+        fi
+        if echo "$reply" | grep -q '"error"' || ! echo "$reply" | grep -q '"result"'; then
+            fail "$label was rejected: $reply"
+        fi
+    done < "$TX_FILE"
+
+    # only record the deployments as done once every transaction was accepted
+    rm -f "$FAILED"
+    touch "$MARKER"
+}
+
+if [ ! -f "$MARKER" ]; then
+    replay &
+fi
+
+exec "$PARITY_BIN" "$@"
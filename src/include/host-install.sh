@@ -0,0 +1,72 @@
+#!/bin/bash
+
+# installs a single node for a host (non-docker) deployment; expects to be run as root
+# from within the node's directory, with CONFIG_DIR, STATE_DIR, SERVICE_USER & UNIT_FILE set.
+
+set -e
+
+cd "$(dirname "$0")"
+
+source pib.env
+
+PROJECT_NAME=${PIB_PROJECT_NAME:?"Expecting PIB_PROJECT_NAME in pib.env"}
+
+KEYS_DIR="$STATE_DIR/keys/$PROJECT_NAME"
+
+# create the service user (if needed)
+if ! id -u "$SERVICE_USER" > /dev/null 2>&1; then
+    useradd --system --no-create-home --shell /usr/sbin/nologin "$SERVICE_USER"
+fi
+
+install -d -m 750 -o root -g "$SERVICE_USER" "$CONFIG_DIR"
+install -d -m 700 -o "$SERVICE_USER" -g "$SERVICE_USER" "$STATE_DIR" "$STATE_DIR/keys" "$KEYS_DIR"
+
+# install config files (account files are consumed below, not installed)
+for file in *
+do
+    if [ -f "$file" ] && [ "$file" != "$(basename "$0")" ] && [ "$file" != "$UNIT_FILE" ]; then
+        install -m 640 -o root -g "$SERVICE_USER" "$file" "$CONFIG_DIR/$file"
+    fi
+done
+
+shopt -s nullglob # set `nullglob` to prevent iteration on empty match
+
+for file in ./accounts/*.env
+do
+    ( # use subshell to sanitize env vars
+
+    # extract variables from file
+    source "$file"
+
+    ACCOUNT_NAME=${PIB_ACCOUNT_NAME:?"Expecing PIB_ACCOUNT_NAME in $file"}
+    ACCOUNT_PASS=${PIB_ACCOUNT_PASS:?"Expecting PIB_ACCOUNT_PASS in $file"}
+    ACCOUNT_SECRET=${PIB_ACCOUNT_SECRET:?"Expecting PIB_ACCOUNT_SECRET in $file"}
+    ACCOUNT_ADDR=${PIB_ACCOUNT_ADDR:?"Expecting PIB_ACCOUNT_ADDR in $file"}
+
+    PASSWORD_FILE="$CONFIG_DIR/${ACCOUNT_NAME}.pass"
+
+    # set up password file
+    ( umask 077 && echo $ACCOUNT_PASS > "$PASSWORD_FILE" )
+    chown root:"$SERVICE_USER" "$PASSWORD_FILE"
+    chmod 640 "$PASSWORD_FILE"
+
+    # insert secret into store (unless a previous install already did so)...
+    if ! ethstore list --dir "$KEYS_DIR" | grep -qi "${ACCOUNT_ADDR#0x}"; then
+        ethstore insert $ACCOUNT_SECRET "$PASSWORD_FILE" --dir "$KEYS_DIR"
+    fi
+
+    # ensure that secret decrypts..
+    ethstore sign $ACCOUNT_ADDR "$PASSWORD_FILE" c82a3ca1f9436de9ffe54faf3fef7e7ac76897e02ba7fd5d013b840fd350d01b --dir "$KEYS_DIR"
+
+    ) # end subshell
+done
+
+shopt -u nullglob # unset `nullglob` (not typically expected behavior
+
+chown -R "$SERVICE_USER:$SERVICE_USER" "$STATE_DIR"
+chmod 600 "$KEYS_DIR"/*
+
+install -m 644 "$UNIT_FILE" "/etc/systemd/system/$UNIT_FILE"
+systemctl daemon-reload
+
+echo "OK"
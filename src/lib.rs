@@ -15,6 +15,10 @@ extern crate serde_yaml;
 extern crate serde;
 extern crate toml;
 extern crate rand;
+extern crate semver;
+extern crate tiny_keccak;
+extern crate tempfile;
+extern crate revm;
 #[macro_use]
 extern crate log;
 
@@ -23,6 +27,7 @@ pub mod options;
 pub mod config;
 pub mod types;
 pub mod util;
+pub mod evm;
 
 
 use std::path::Path;
@@ -40,7 +45,8 @@ pub fn setup(options: SetupOptions) -> Result<(),Error> {
         options.authorities,
         options.interfaces,
         options.actors,
-        );
+        options.subnet,
+        )?;
 
     let mut ctx = ProjectContext::new(project_config);
 
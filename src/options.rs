@@ -1,4 +1,5 @@
 //use structopt::StructOpt;
+use types::Subnet;
 use std::path::PathBuf;
 
 
@@ -16,6 +17,9 @@ pub struct SetupOptions {
     /// Number of actor accounts to generate
     #[structopt(name = "actor-count",long = "actors",default_value="0")]
     pub actors: u16,
+    /// Subnet from which node addresses are allocated (e.g. `172.28.0.0/16`)
+    #[structopt(long = "subnet")]
+    pub subnet: Option<Subnet>,
     #[structopt(long = "no-examples")]
     /// Do not generate examples
     pub no_examples: bool,
@@ -32,9 +36,18 @@ pub struct BuildOptions {
     #[structopt(name = "dir", long = "output", default_value = "output")]
     #[structopt(parse(from_os_str))]
     pub output_dir: PathBuf,
-    /// Do not invoke solc
+    /// Use precompiled artifacts instead of invoking compilers
     #[structopt(long = "no-solc")]
     pub no_solc: bool, 
+    /// Recompile all contracts, ignoring cached builds
+    #[structopt(long = "no-cache")]
+    pub no_cache: bool,
+    /// Do not execute genesis constructors before writing the chain spec
+    #[structopt(long = "skip-dry-run")]
+    pub skip_dry_run: bool,
+    /// Write contract binaries & ABIs to the output directory
+    #[structopt(long = "export-contracts")]
+    pub export_contracts: bool,
     /// Overwrite existing files
     #[structopt(long = "force")]
     pub force: bool,
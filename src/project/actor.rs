@@ -26,6 +26,8 @@ impl InternalActor {
 
     pub fn secret(&self) -> Secret { self.signer.secret() }
 
+    pub fn signer(&self) -> &Signer { &self.signer }
+
     pub fn address(&self) -> Address { self.signer.address() }
 
     pub fn password(&self) -> &str { &self.actor_pass }
@@ -0,0 +1,452 @@
+//! Reference contracts shipped with pib.
+//!
+//! Bundled sources are staged into a dedicated subdirectory of the compiler
+//! scratch directory, so they never collide with the project's own sources.
+use mimir_crypto::secp256k1::Address;
+use project::Project;
+use project::contract::ContractConfig;
+use project::node::NodeRole;
+use project::storage::{self,Storage,Word};
+use types::Error;
+use util;
+use std::collections::{HashMap,HashSet};
+use std::path::Path;
+
+
+/// Scratch subdirectory into which bundled sources are staged
+pub const BUNDLED_DIR: &str = "pib";
+
+/// Filename of the bundled validator-set contracts
+pub const VALIDATOR_SET_FILENAME: &str = "ValidatorSet.sol";
+
+/// Source of the bundled validator-set contracts
+pub const VALIDATOR_SET_SOURCE: &str = include_str!("../include/ValidatorSet.sol");
+
+/// Config of the bundled non-reporting validator set (`safeContract`)
+pub const SIMPLE_VALIDATOR_SET_CONFIG: &str = r#"
+name = "SimpleValidatorSet"
+addr = "0x0000000000000000000000000000000000001000"
+source = "pib/ValidatorSet.sol"
+compiler = "solc"
+args = ["authority-addrs"]
+role = "validator-set-simple"
+"#;
+
+/// Config of the bundled reporting validator set (`contract`)
+pub const REPORTING_VALIDATOR_SET_CONFIG: &str = r#"
+name = "ReportingValidatorSet"
+addr = "0x0000000000000000000000000000000000001000"
+source = "pib/ValidatorSet.sol"
+compiler = "solc"
+args = ["authority-addrs"]
+role = "validator-set-reporting"
+"#;
+
+
+/// Filename of the bundled name registry
+pub const REGISTRY_FILENAME: &str = "NameRegistry.sol";
+
+/// Source of the bundled name registry
+pub const REGISTRY_SOURCE: &str = include_str!("../include/NameRegistry.sol");
+
+/// Name of the bundled name registry contract
+pub const REGISTRY_NAME: &str = "NameRegistry";
+
+/// Config of the bundled name registry (storage is populated at genesis)
+pub const REGISTRY_CONFIG: &str = r#"
+name = "NameRegistry"
+addr = "0x0000000000000000000000000000000000001001"
+source = "pib/NameRegistry.sol"
+compiler = "solc"
+"#;
+
+
+/// Filename of the bundled ERC-20 token
+pub const TOKEN_FILENAME: &str = "StandardToken.sol";
+
+/// Source of the bundled ERC-20 token
+pub const TOKEN_SOURCE: &str = include_str!("../include/StandardToken.sol");
+
+/// Name of the bundled ERC-20 token contract
+pub const TOKEN_NAME: &str = "StandardToken";
+
+
+/// Filename of the bundled permissioning contracts
+pub const PERMISSIONS_FILENAME: &str = "Permissions.sol";
+
+/// Source of the bundled permissioning contracts
+pub const PERMISSIONS_SOURCE: &str = include_str!("../include/Permissions.sol");
+
+/// Name of the bundled transaction permissioning contract
+pub const TX_PERMISSION_NAME: &str = "TxPermission";
+
+/// Config of the bundled transaction permissioning contract (storage is populated at genesis)
+pub const TX_PERMISSION_CONFIG: &str = r#"
+name = "TxPermission"
+addr = "0x0000000000000000000000000000000000001002"
+source = "pib/Permissions.sol"
+compiler = "solc"
+role = "transaction-permission"
+"#;
+
+/// Name of the bundled node permissioning contract
+pub const NODE_PERMISSION_NAME: &str = "NodePermission";
+
+/// Config of the bundled node permissioning contract (storage is populated at genesis)
+pub const NODE_PERMISSION_CONFIG: &str = r#"
+name = "NodePermission"
+addr = "0x0000000000000000000000000000000000001003"
+source = "pib/Permissions.sol"
+compiler = "solc"
+role = "node-permission"
+"#;
+
+
+/// Filename of the bundled network topology contract
+pub const TOPOLOGY_FILENAME: &str = "NetworkTopology.sol";
+
+/// Source of the bundled network topology contract
+pub const TOPOLOGY_SOURCE: &str = include_str!("../include/NetworkTopology.sol");
+
+/// Name of the bundled network topology contract
+pub const TOPOLOGY_NAME: &str = "NetworkTopology";
+
+/// Config of the bundled network topology contract (storage is populated at genesis)
+pub const TOPOLOGY_CONFIG: &str = r#"
+name = "NetworkTopology"
+addr = "0x0000000000000000000000000000000000001004"
+source = "pib/NetworkTopology.sol"
+compiler = "solc"
+"#;
+
+
+/// All bundled sources, as `(filename,source)` pairs
+const SOURCES: &[(&str,&str)] = &[
+    (VALIDATOR_SET_FILENAME,VALIDATOR_SET_SOURCE),
+    (REGISTRY_FILENAME,REGISTRY_SOURCE),
+    (TOKEN_FILENAME,TOKEN_SOURCE),
+    (PERMISSIONS_FILENAME,PERMISSIONS_SOURCE),
+    (TOPOLOGY_FILENAME,TOPOLOGY_SOURCE),
+];
+
+
+/// Selects how the genesis validator set is specified
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValidatorMode {
+    /// Static list of authority addresses
+    List,
+    /// Bundled `SimpleValidatorSet` as `safeContract`
+    SafeContract,
+    /// Bundled `ReportingValidatorSet` as `contract`
+    Contract,
+}
+
+
+impl Default for ValidatorMode {
+
+    fn default() -> Self { ValidatorMode::List }
+}
+
+
+impl ValidatorMode {
+
+    /// Config of the bundled contract implementing this mode (if any).
+    pub fn contract_config(&self) -> Option<ContractConfig> {
+        let raw = match self {
+            ValidatorMode::List => return None,
+            ValidatorMode::SafeContract => SIMPLE_VALIDATOR_SET_CONFIG,
+            ValidatorMode::Contract => REPORTING_VALIDATOR_SET_CONFIG,
+        };
+        Some(raw.parse().expect("bundled config must parse"))
+    }
+}
+
+
+/// Config of the bundled name registry.
+pub fn registry_config() -> ContractConfig {
+    REGISTRY_CONFIG.parse().expect("bundled config must parse")
+}
+
+
+/// Storage of the name registry, mapping the name of every account to its
+/// address and vice versa.  Where names are shared, the first account wins.
+pub fn registry_storage(project: &Project) -> Storage {
+    let (addresses,names) = (storage::slot(0),storage::slot(1));
+    let mut state = Storage::default();
+    let mut seen = HashSet::new();
+    for account in project.iter_accounts() {
+        if !seen.insert(account.name()) {
+            warn!("name `{}` is used by multiple accounts; registering the first only",account.name());
+            continue;
+        }
+        let name_slot = storage::mapping_slot(addresses,account.name().as_bytes());
+        state.set_address(name_slot,account.address());
+        let addr_slot = storage::mapping_slot(names,&storage::address_word(account.address()));
+        state.set_string(addr_slot,account.name());
+    }
+    state
+}
+
+
+/// Config of the bundled network topology contract.
+pub fn topology_config() -> ContractConfig {
+    TOPOLOGY_CONFIG.parse().expect("bundled config must parse")
+}
+
+
+/// Storage of the network topology contract, recording the name, role, enode and
+/// account of every node along with its reserved peers.  Authorities are admins.
+pub fn topology_storage(project: &Project) -> Storage {
+    let (nodes,index_of) = (storage::slot(0),storage::slot(1));
+    let (peers,admins) = (storage::slot(2),storage::slot(3));
+    let indices: HashMap<&str,u64> = project.iter_nodes().enumerate()
+        .map(|(index,node)| (node.name(),index as u64))
+        .collect();
+    let mut state = Storage::default();
+    state.set_uint(nodes,indices.len() as u64);
+    let start = storage::keccak(&nodes);
+    for (index,node) in project.iter_nodes().enumerate() {
+        let index = index as u64;
+        let base = storage::offset(start,index * NODE_SLOTS);
+        let role = match node.node_role() {
+            NodeRole::Authority => 0,
+            NodeRole::Interface => 1,
+        };
+        state.set_string(base,node.name());
+        state.set_uint(storage::offset(base,1),role);
+        state.set_string(storage::offset(base,2),&node.enode_addr().to_string());
+        state.set_address(storage::offset(base,3),node.account_addr());
+        let account = storage::address_word(node.account_addr());
+        state.set_uint(storage::mapping_slot(index_of,&account),index + 1);
+        let peer_indices: Vec<Word> = project.iter_peers(node.name())
+            .map(|peer| storage::uint_word(indices[peer.name()]))
+            .collect();
+        state.set_array(storage::mapping_slot(peers,&storage::uint_word(index)),&peer_indices);
+        if node.is_authority() {
+            state.set_uint(storage::mapping_slot(admins,&account),1);
+        }
+    }
+    state
+}
+
+
+/// Number of storage slots occupied by each node record of `NetworkTopology`
+const NODE_SLOTS: u64 = 4;
+
+
+/// Permissioning of transactions & peer connections via the bundled contracts
+///
+/// ```toml
+/// [chain.permissions]
+/// transactions = "trusted"
+/// nodes = "consortium"
+/// admins = "operator"
+/// ```
+///
+/// Each setting names a tag.  Admins may modify both allow-lists after genesis,
+/// and are themselves permitted to transact.
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct PermissionsConfig {
+    /// Tag of the accounts permitted to send transactions (enables `TxPermission`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<String>,
+
+    /// Tag of the nodes permitted to connect to one another (enables `NodePermission`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<String>,
+
+    /// Tag of the accounts permitted to modify the allow-lists
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub admins: Option<String>,
+}
+
+
+impl PermissionsConfig {
+
+    /// Configs of the bundled contracts enabled by these settings.
+    pub fn contract_configs(&self) -> Vec<ContractConfig> {
+        let mut configs = Vec::new();
+        if self.transactions.is_some() {
+            configs.push(TX_PERMISSION_CONFIG.parse().expect("bundled config must parse"));
+        }
+        if self.nodes.is_some() {
+            configs.push(NODE_PERMISSION_CONFIG.parse().expect("bundled config must parse"));
+        }
+        configs
+    }
+
+    /// Storage of `TxPermission`, allowing all tagged accounts & admins to transact.
+    pub fn tx_storage(&self, project: &Project) -> Option<Storage> {
+        let tag = self.transactions.as_ref()?;
+        let allowed = storage::slot(0);
+        let mut state = self.admin_storage(project);
+        let senders = project.iter_accounts()
+            .filter(|account| account.tags().contains(tag))
+            .map(|account| account.address())
+            .chain(self.admin_addrs(project));
+        for addr in senders {
+            let slot = storage::mapping_slot(allowed,&storage::address_word(addr));
+            state.set_uint(slot,1);
+        }
+        if state.is_empty() {
+            warn!("no accounts are tagged `{}`; all transactions will be rejected",tag);
+        }
+        Some(state)
+    }
+
+    /// Storage of `NodePermission`, allowing all tagged nodes to connect.
+    pub fn node_storage(&self, project: &Project) -> Option<Storage> {
+        let tag = self.nodes.as_ref()?;
+        let allowed = storage::slot(0);
+        let mut state = self.admin_storage(project);
+        for node in project.iter_nodes() {
+            if node.tags().contains(tag) {
+                let enode = node.enode_addr();
+                let slot = storage::mapping_slot(allowed,&storage::keccak(&enode.public));
+                state.set_uint(slot,1);
+            } else if node.internal().is_some() {
+                warn!("node `{}` is not tagged `{}` and will be unable to connect",node.name(),tag);
+            }
+        }
+        Some(state)
+    }
+
+    /// Storage of the `admins` mapping shared by both contracts.
+    fn admin_storage(&self, project: &Project) -> Storage {
+        let admins = storage::slot(1);
+        let mut state = Storage::default();
+        for addr in self.admin_addrs(project) {
+            let slot = storage::mapping_slot(admins,&storage::address_word(addr));
+            state.set_uint(slot,1);
+        }
+        state
+    }
+
+    fn admin_addrs<'a>(&'a self, project: &'a Project) -> impl Iterator<Item=Address> + 'a {
+        project.iter_accounts()
+            .filter(move |account| match self.admins.as_ref() {
+                Some(tag) => account.tags().contains(tag),
+                None => false,
+            })
+            .map(|account| account.address())
+    }
+}
+
+
+/// Copy all bundled sources into the scratch directory.
+pub fn stage_sources(scratch_dir: &Path) -> Result<(),Error> {
+    for (filename,source) in SOURCES.iter() {
+        util::save(scratch_dir.join(BUNDLED_DIR).join(filename),source)?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use project::Project;
+    use project::storage::{self,Storage,Word};
+    use project::tests::project;
+    use super::{registry_storage,topology_storage};
+
+    /// `node-0` is the only authority & the hub of a star.
+    const NODES: &str = r#"
+        [[internal]]
+        node-name = "node-0"
+        node-role = "authority"
+        network-addr = "10.0.0.2:30303"
+        tags = ["hub"]
+
+        [[internal]]
+        node-name = "node-1"
+        network-addr = "10.0.0.3:30303"
+
+        [[internal]]
+        node-name = "node-2"
+        network-addr = "10.0.0.4:30303"
+    "#;
+
+    /// The first actor shares its name with `node-0`.
+    const ACTORS: &str = r#"
+        [[external]]
+        actor-name = "node-0"
+        address = "0x00000000000000000000000000000000000000aa"
+
+        [[external]]
+        actor-name = "operator"
+        address = "0x00000000000000000000000000000000000000bb"
+    "#;
+
+    const NETWORK: &str = r#"
+        topology = "star"
+        hubs = "hub"
+    "#;
+
+    fn fixture() -> Project { project(NODES,ACTORS,NETWORK) }
+
+    /// Value stored at `slot` (zero if unset, as per the EVM).
+    fn load(state: &Storage, slot: Word) -> Word {
+        state.iter().find(|(key,_)| **key == slot).map(|(_,value)| *value).unwrap_or([0u8;32])
+    }
+
+    fn short_string(value: &str) -> Word {
+        let mut word = [0u8;32];
+        word[..value.len()].copy_from_slice(value.as_bytes());
+        word[31] = value.len() as u8 * 2;
+        word
+    }
+
+    #[test]
+    fn registry_maps_names_both_ways() {
+        let project = fixture();
+        let state = registry_storage(&project);
+        let (addresses,names) = (storage::slot(0),storage::slot(1));
+        let accounts: Vec<(&str,_)> = project.iter_accounts()
+            .map(|account| (account.name(),account.address()))
+            .collect();
+        assert_eq!(accounts.len(),5);
+        // the actor named `node-0` is shadowed by the node itself.
+        for (name,addr) in accounts[..3].iter().chain(accounts[4..].iter()) {
+            let name_slot = storage::mapping_slot(addresses,name.as_bytes());
+            assert_eq!(load(&state,name_slot),storage::address_word(*addr));
+            let addr_slot = storage::mapping_slot(names,&storage::address_word(*addr));
+            assert_eq!(load(&state,addr_slot),short_string(name));
+        }
+        let shadowed = storage::mapping_slot(names,&storage::address_word(accounts[3].1));
+        assert_eq!(load(&state,shadowed),[0u8;32]);
+        assert_eq!(state.iter().count(),8);
+    }
+
+    #[test]
+    fn topology_records_nodes_and_peers() {
+        let project = fixture();
+        let state = topology_storage(&project);
+        let (nodes,index_of) = (storage::slot(0),storage::slot(1));
+        let (peers,admins) = (storage::slot(2),storage::slot(3));
+        assert_eq!(load(&state,nodes),storage::uint_word(3));
+        let start = storage::keccak(&nodes);
+        let expected_peers: &[&[u64]] = &[&[1,2],&[0],&[0]];
+        for (index,node) in project.iter_nodes().enumerate() {
+            let base = storage::offset(start,index as u64 * 4);
+            assert_eq!(load(&state,base),short_string(node.name()));
+            let role = if index == 0 { 0 } else { 1 };
+            assert_eq!(load(&state,storage::offset(base,1)),storage::uint_word(role));
+            let enode = node.enode_addr().to_string();
+            assert!(enode.len() > 31);
+            assert_eq!(load(&state,storage::offset(base,2)),storage::uint_word(enode.len() as u64 * 2 + 1));
+            let account = storage::address_word(node.account_addr());
+            assert_eq!(load(&state,storage::offset(base,3)),account);
+            assert_eq!(load(&state,storage::mapping_slot(index_of,&account)),storage::uint_word(index as u64 + 1));
+            let array = storage::mapping_slot(peers,&storage::uint_word(index as u64));
+            assert_eq!(load(&state,array),storage::uint_word(expected_peers[index].len() as u64));
+            for (position,peer) in expected_peers[index].iter().enumerate() {
+                let slot = storage::offset(storage::keccak(&array),position as u64);
+                assert_eq!(load(&state,slot),storage::uint_word(*peer));
+            }
+            let admin = if node.is_authority() { 1 } else { 0 };
+            assert_eq!(load(&state,storage::mapping_slot(admins,&account)),storage::uint_word(admin));
+        }
+    }
+}
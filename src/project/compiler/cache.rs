@@ -0,0 +1,108 @@
+//! On-disk cache of compiled contract artifacts.
+use types::Error;
+use serde_json::{self,Value};
+use tiny_keccak::{self,Keccak};
+use util;
+use std::collections::HashMap;
+use std::path::{Path,PathBuf};
+use std::fs;
+
+
+/// Default location of the build cache (relative to the project directory)
+pub const CACHE_DIR: &str = ".pib/cache";
+
+
+/// Cached compiler output
+#[derive(Debug,Clone,Serialize,Deserialize)]
+struct CacheEntry {
+    /// Hashes of all source files used in the compilation
+    sources: HashMap<String,String>,
+    /// Compiler output for the contract
+    output: Value,
+}
+
+
+/// Compilation cache keyed by compiler input.
+///
+/// Entries record the hash of every source file involved in the compilation
+/// (including imports), and are only considered valid if all of those files
+/// are unchanged.
+///
+#[derive(Debug,Clone)]
+pub struct Cache {
+    root: PathBuf,
+    enabled: bool,
+}
+
+
+impl Cache {
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), enabled: true }
+    }
+
+    /// Skip cache lookups (results are still stored).
+    pub fn lookups(mut self, enabled: bool) -> Self { self.enabled = enabled; self }
+
+    /// Derive a cache key from all inputs which affect compiler output.
+    pub fn key(parts: &[&str]) -> String {
+        let mut keccak = Keccak::new_keccak256();
+        for part in parts.iter() {
+            keccak.update(format!("{}:",part.len()).as_bytes());
+            keccak.update(part.as_bytes());
+        }
+        let mut hash = [0u8;32];
+        keccak.finalize(&mut hash);
+        util::hex_string(&hash)
+    }
+
+    /// Load cached output, provided that none of its sources (relative to `dir`) have changed.
+    pub fn load(&self, key: &str, dir: &Path) -> Option<Value> {
+        if !self.enabled {
+            return None;
+        }
+        let raw = fs::read_to_string(self.entry_path(key)).ok()?;
+        let entry: CacheEntry = match serde_json::from_str(&raw) {
+            Ok(entry) => entry,
+            Err(err) => {
+                debug!("ignoring malformed cache entry {}: {}",key,err);
+                return None;
+            }
+        };
+        for (source,hash) in entry.sources.iter() {
+            if hash_file(&dir.join(source)).as_ref() != Some(hash) {
+                debug!("cache entry {} is stale (`{}` changed)",key,source);
+                return None;
+            }
+        }
+        Some(entry.output)
+    }
+
+    /// Store compiler output along with the hashes of the sources it was built from.
+    pub fn store<'a>(&self, key: &str, dir: &Path, sources: impl IntoIterator<Item=&'a str>, output: &Value) -> Result<(),Error> {
+        let mut hashes = HashMap::new();
+        for source in sources {
+            match hash_file(&dir.join(source)) {
+                Some(hash) => { hashes.insert(source.to_owned(),hash); },
+                None => {
+                    let msg = format!("unable to read `{}` for caching",source);
+                    return Err(Error::message(msg));
+                }
+            }
+        }
+        let entry = CacheEntry { sources: hashes, output: output.clone() };
+        let serialized = serde_json::to_string(&entry)?;
+        util::save(self.entry_path(key),serialized)?;
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.root.join(key).with_extension("json")
+    }
+}
+
+
+fn hash_file(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    Some(util::hex_string(&tiny_keccak::keccak256(&data)))
+}
@@ -0,0 +1,171 @@
+//! Library placeholders & linking.
+use mimir_crypto::secp256k1::Address;
+use mimir_types::Bytes;
+use types::Error;
+use serde_json::Value;
+use tiny_keccak;
+use util;
+use std::collections::HashMap;
+
+
+/// Length (in hex characters) of a library placeholder
+const PLACEHOLDER_LEN: usize = 40;
+
+
+/// Addresses of linkable libraries.
+///
+/// Libraries are keyed both by contract name and by fully-qualified
+/// name (`<source-file>:<name>`), matching the identifiers used by solc.
+///
+#[derive(Default,Debug,Clone)]
+pub struct Libraries {
+    inner: HashMap<String,Address>,
+}
+
+
+impl Libraries {
+
+    pub fn insert(&mut self, source: &str, name: &str, addr: Address) {
+        self.inner.insert(name.to_owned(),addr);
+        self.inner.insert(format!("{}:{}",source,name),addr);
+    }
+
+    /// Resolve a library referenced by its source file & name.
+    fn get(&self, source: &str, name: &str) -> Option<Address> {
+        self.inner.get(&format!("{}:{}",source,name))
+            .or_else(|| self.inner.get(name))
+            .cloned()
+    }
+
+    /// Resolve the identifier embedded in a raw placeholder (`__$<hash>$__` or `__<name>___`),
+    /// with the leading `__` already removed.
+    fn resolve_placeholder(&self, ident: &str) -> Result<Option<Address>,Error> {
+        if ident.starts_with('$') && ident.ends_with('$') {
+            let hash = ident.trim_matches('$');
+            let addr = self.inner.iter()
+                .filter(|(key,_)| key.contains(':'))
+                .find(|(key,_)| util::hex_string(&tiny_keccak::keccak256(key.as_bytes())).starts_with(hash))
+                .map(|(_,addr)| *addr);
+            Ok(addr)
+        } else {
+            // only the trailing `_` are padding; leading underscores belong to the identifier.
+            let ident = ident.trim_right_matches('_');
+            if ident.is_empty() {
+                return Ok(None);
+            }
+            if let Some(addr) = self.inner.get(ident) {
+                return Ok(Some(*addr));
+            }
+            // legacy placeholders may truncate long identifiers.
+            let name = ident.rsplit(':').next().unwrap_or(ident);
+            let mut matches: Vec<(&str,Address)> = self.inner.iter()
+                .filter(|(key,_)| key.starts_with(ident) || key.as_str() == name)
+                .map(|(key,addr)| (key.as_str(),*addr))
+                .collect();
+            matches.sort_by(|a,b| a.0.cmp(b.0));
+            // each library is keyed both by name & by qualified name, so count distinct addresses.
+            let mut addrs: Vec<Address> = Vec::new();
+            for (_,addr) in matches.iter() {
+                if !addrs.iter().any(|other| other.into_inner() == addr.into_inner()) {
+                    addrs.push(*addr);
+                }
+            }
+            match addrs.len() {
+                0 => Ok(None),
+                1 => Ok(Some(addrs[0])),
+                _ => {
+                    let keys: Vec<&str> = matches.iter().map(|(key,_)| *key).collect();
+                    let msg = format!("ambiguous library placeholder `{}` (matches {})",ident,keys.join(", "));
+                    Err(Error::message(msg))
+                },
+            }
+        }
+    }
+}
+
+
+/// A library referenced from within contract bytecode
+#[derive(Debug,Clone)]
+enum LibraryRef {
+    /// Library identified by source file & name (via standard-json `linkReferences`)
+    Named { source: String, name: String },
+    /// Library identified only by its raw placeholder text
+    Placeholder(String),
+}
+
+
+/// Contract bytecode which may contain unresolved library placeholders
+#[derive(Debug,Clone)]
+pub struct Bytecode {
+    object: String,
+    references: Vec<(usize,LibraryRef)>,
+}
+
+
+impl Bytecode {
+
+    /// Load hex-encoded bytecode (e.g. a `.bin` file), locating any placeholders.
+    pub fn from_object(object: &str) -> Result<Self,Error> {
+        let object = object.trim().trim_left_matches("0x").to_owned();
+        let mut references = Vec::new();
+        let mut index = 0;
+        while let Some(found) = object[index..].find("__") {
+            let start = index + found;
+            let end = start + PLACEHOLDER_LEN;
+            if end > object.len() {
+                return Err(Error::message("bytecode contains a truncated library placeholder"));
+            }
+            let ident = object[start + 2..end - 2].to_owned();
+            references.push((start,LibraryRef::Placeholder(ident)));
+            index = end;
+        }
+        Ok(Self { object, references })
+    }
+
+    /// Load bytecode from solc's standard-json output.
+    pub fn from_standard_json(object: &str, link_references: Option<&Value>) -> Result<Self,Error> {
+        let object = object.trim().trim_left_matches("0x").to_owned();
+        let mut references = Vec::new();
+        let sources = link_references.and_then(Value::as_object).into_iter().flat_map(|m| m.iter());
+        for (source,libraries) in sources {
+            for (name,positions) in libraries.as_object().into_iter().flat_map(|m| m.iter()) {
+                for position in positions.as_array().into_iter().flat_map(|a| a.iter()) {
+                    let start = position.get("start").and_then(Value::as_u64)
+                        .ok_or_else(|| Error::message("malformed link reference"))?;
+                    let library = LibraryRef::Named { source: source.to_owned(), name: name.to_owned() };
+                    references.push((start as usize * 2,library));
+                }
+            }
+        }
+        Ok(Self { object, references })
+    }
+
+    /// Check if the bytecode contains any library placeholders.
+    pub fn is_linked(&self) -> bool { self.references.is_empty() }
+
+    /// Replace all placeholders with library addresses, producing final bytecode.
+    pub fn link(&self, libraries: &Libraries) -> Result<Bytes,Error> {
+        let mut object = self.object.clone();
+        for (offset,library) in self.references.iter() {
+            let (resolved,ident) = match library {
+                LibraryRef::Named { source, name } => (libraries.get(source,name),name.as_str()),
+                LibraryRef::Placeholder(ident) => (libraries.resolve_placeholder(ident)?,ident.as_str()),
+            };
+            match resolved {
+                Some(addr) if offset + PLACEHOLDER_LEN <= object.len() => {
+                    object.replace_range(*offset..*offset + PLACEHOLDER_LEN,&util::hex_string(&addr));
+                },
+                Some(_) => {
+                    return Err(Error::message("library link reference out of range"));
+                },
+                None => {
+                    let msg = format!("unresolved library `{}` (expected a contract entry of the same name)",
+                        ident.trim_right_matches('_'));
+                    return Err(Error::message(msg));
+                },
+            }
+        }
+        let code = object.parse()?;
+        Ok(code)
+    }
+}
@@ -0,0 +1,247 @@
+//! Contract compilation.
+//!
+pub mod solc;
+pub mod vyper;
+pub mod precompiled;
+pub mod link;
+pub mod cache;
+
+pub use self::solc::{SolcConfig,Solidity};
+pub use self::vyper::{VyperConfig,Vyper};
+pub use self::precompiled::Precompiled;
+pub use self::link::{Bytecode,Libraries};
+pub use self::cache::Cache;
+
+use project::contract::ContractConfig;
+use project::bundled;
+use ethabi::Contract as Abi;
+use serde_json::{self,Value};
+use types::Error;
+use util;
+use std::path::{Path,PathBuf};
+use std::ops::Deref;
+use std::fs;
+
+
+/// A contract compiler backend.
+pub trait Compiler {
+
+    /// Produce the artifact for `config` from `source` (relative to the contract directory).
+    fn compile(&self, ctx: &CompileContext, config: &ContractConfig, source: &str) -> Result<Artifact,Error>;
+}
+
+
+/// Selects the compiler backend used for a contract
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompilerKind {
+    /// Solidity sources (`.sol`)
+    Solc,
+    /// Vyper sources (`.vy`)
+    Vyper,
+    /// Existing artifacts (`.bin`, or truffle/hardhat `.json`)
+    Precompiled,
+}
+
+
+impl CompilerKind {
+
+    /// Infer the compiler from the extension of a source file.
+    pub fn from_source(source: &str) -> Option<Self> {
+        match Path::new(source).extension().and_then(|ext| ext.to_str()) {
+            Some("sol") => Some(CompilerKind::Solc),
+            Some("vy") => Some(CompilerKind::Vyper),
+            Some("bin") | Some("json") => Some(CompilerKind::Precompiled),
+            _ => None,
+        }
+    }
+
+    /// File extensions handled by this compiler, in order of preference.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            CompilerKind::Solc => &["sol"],
+            CompilerKind::Vyper => &["vy"],
+            CompilerKind::Precompiled => &["bin","json"],
+        }
+    }
+
+    /// Get the backend implementing this compiler.
+    pub fn backend(&self) -> &'static dyn Compiler {
+        match self {
+            CompilerKind::Solc => &Solidity,
+            CompilerKind::Vyper => &Vyper,
+            CompilerKind::Precompiled => &Precompiled,
+        }
+    }
+}
+
+
+/// Source extensions tried (in order) when a contract does not specify its source
+const SOURCE_EXTENSIONS: &[&str] = &["sol","vy"];
+
+
+/// Settings & shared state used when loading a project's contracts
+#[derive(Debug,Clone)]
+pub struct CompileContext {
+    /// Directory containing contract sources & binaries
+    pub contract_dir: PathBuf,
+    /// Scratch directory in which compilers are run
+    pub scratch_dir: PathBuf,
+    /// Project-wide solc settings
+    pub solc: SolcConfig,
+    /// Project-wide vyper settings
+    pub vyper: VyperConfig,
+    /// Addresses of linkable libraries
+    pub libraries: Libraries,
+    /// Cache of compiled artifacts
+    pub cache: Cache,
+    /// Load precompiled artifacts instead of invoking compilers
+    pub no_solc: bool,
+}
+
+
+impl CompileContext {
+
+    /// Locate the source of a contract & select the compiler responsible for it.
+    pub fn resolve(&self, config: &ContractConfig) -> Result<(CompilerKind,String),Error> {
+        if self.no_solc {
+            if let Some(source) = bundled_source(config) {
+                return self.resolve_bundled(config,source);
+            }
+        }
+        let source = match config.source.as_ref() {
+            Some(source) if !self.no_solc => source.to_owned(),
+            _ => {
+                let extensions = match config.compiler {
+                    _ if self.no_solc => CompilerKind::Precompiled.extensions(),
+                    Some(kind) => kind.extensions(),
+                    None => SOURCE_EXTENSIONS,
+                };
+                let candidates: Vec<_> = extensions.iter()
+                    .map(|ext| format!("{}.{}",config.name,ext))
+                    .collect();
+                match candidates.iter().find(|c| self.contract_dir.join(c).is_file()) {
+                    Some(found) => found.to_owned(),
+                    None => {
+                        let msg = format!("unable to locate `{}`",candidates.join("` or `"));
+                        return Err(Error::message(msg));
+                    }
+                }
+            }
+        };
+        let kind = if self.no_solc {
+            CompilerKind::Precompiled
+        } else {
+            match config.compiler.or_else(|| CompilerKind::from_source(&source)) {
+                Some(kind) => kind,
+                None => {
+                    let msg = format!("unable to infer compiler for `{}` (specify `compiler`)",source);
+                    return Err(Error::message(msg));
+                }
+            }
+        };
+        Ok((kind,source))
+    }
+
+    /// Bundled contracts are exempt from `--no-solc`: a precompiled artifact is used if one
+    /// exists, & the bundled source is compiled otherwise.
+    fn resolve_bundled(&self, config: &ContractConfig, source: &str) -> Result<(CompilerKind,String),Error> {
+        let candidates: Vec<_> = CompilerKind::Precompiled.extensions().iter()
+            .map(|ext| format!("{}.{}",config.name,ext))
+            .collect();
+        if let Some(found) = candidates.iter().find(|c| self.contract_dir.join(c).is_file()) {
+            return Ok((CompilerKind::Precompiled,found.to_owned()));
+        }
+        if let Err(err) = self.solc.merged(config.solc.as_ref()).compiler_version() {
+            let msg = format!("bundled contract `{}` must be compiled with solc, even with `--no-solc` ({}); \
+                alternatively, supply `{}` (with `{}.bin-runtime` if its storage is set at genesis)",
+                config.name,err,candidates.join("` or `"),config.name);
+            return Err(Error::message(msg));
+        }
+        info!("compiling bundled contract `{}` (no precompiled artifact found)",config.name);
+        Ok((CompilerKind::Solc,source.to_owned()))
+    }
+}
+
+
+/// Source of a bundled contract (staged under `bundled::BUNDLED_DIR`), if `config` is one.
+fn bundled_source(config: &ContractConfig) -> Option<&str> {
+    let source = config.source.as_ref()?;
+    if Path::new(source).starts_with(bundled::BUNDLED_DIR) {
+        Some(source)
+    } else {
+        None
+    }
+}
+
+
+/// Output of a successful contract compilation
+#[derive(Debug,Clone)]
+pub struct Artifact {
+    /// Constructor (init) code, possibly awaiting library linking
+    pub code: Bytecode,
+    /// Runtime (deployed) code, if known
+    pub runtime: Option<Bytecode>,
+    /// Contract ABI (if known)
+    pub abi: Option<ContractAbi>,
+}
+
+
+/// Contract ABI which retains its original JSON representation
+#[derive(Debug,Clone)]
+pub struct ContractAbi {
+    json: Value,
+    abi: Abi,
+}
+
+
+impl ContractAbi {
+
+    pub fn from_json(json: Value) -> Result<Self,Error> {
+        let abi = serde_json::from_value(json.clone())?;
+        Ok(Self { json, abi })
+    }
+
+    /// Get the JSON representation of the ABI.
+    pub fn json(&self) -> &Value { &self.json }
+}
+
+
+impl Deref for ContractAbi {
+
+    type Target = Abi;
+
+    fn deref(&self) -> &Self::Target { &self.abi }
+}
+
+
+/// Copy contract sources into the scratch directory so that compilers never
+/// write to (or read stray outputs from) the project tree.  Pre-compiled
+/// binaries & ABIs are left where they are.
+pub fn stage_sources(contract_dir: &Path, scratch_dir: &Path) -> Result<(),Error> {
+    if contract_dir.is_dir() {
+        for file in util::load_dir(contract_dir) {
+            let (name,data) = file?;
+            let is_source = name.extension().and_then(|ext| ext.to_str())
+                .map(|ext| SOURCE_EXTENSIONS.contains(&ext))
+                .unwrap_or(false);
+            if is_source {
+                util::save(scratch_dir.join(name),data)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/// Resolve the path of a compiler executable.
+///
+/// Relative paths must not be resolved from the scratch directory in which
+/// compilers are run, so they are made absolute up front.
+pub fn resolve_binary(path: &Path) -> Result<PathBuf,Error> {
+    if path.components().count() > 1 {
+        Ok(fs::canonicalize(path)?)
+    } else {
+        Ok(path.to_owned())
+    }
+}
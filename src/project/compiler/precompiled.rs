@@ -0,0 +1,88 @@
+//! Loading of precompiled contract artifacts.
+use project::compiler::{Compiler,CompileContext,Artifact,Bytecode,ContractAbi};
+use project::contract::ContractConfig;
+use types::Error;
+use serde_json::{self,Value};
+use std::path::Path;
+use std::fs;
+
+
+/// Backend for existing artifacts.
+///
+/// Supports raw `.bin` files (with optional `.bin-runtime` & `.abi` files alongside), as well
+/// as the `.json` artifacts produced by truffle and hardhat.
+///
+pub struct Precompiled;
+
+
+impl Compiler for Precompiled {
+
+    fn compile(&self, ctx: &CompileContext, config: &ContractConfig, source: &str) -> Result<Artifact,Error> {
+        let path = ctx.contract_dir.join(source);
+        debug!("loading existing artifact {:?}",path);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => load_json(&path,config.contract_name()),
+            _ => load_binary(&path),
+        }
+    }
+}
+
+
+/// Load a raw binary, along with its runtime code & ABI if present.
+fn load_binary(path: &Path) -> Result<Artifact,Error> {
+    let code = Bytecode::from_object(&fs::read_to_string(path)?)?;
+    let runtime_path = path.with_extension("bin-runtime");
+    let runtime = if runtime_path.is_file() {
+        Some(Bytecode::from_object(&fs::read_to_string(runtime_path)?)?)
+    } else {
+        None
+    };
+    let abi_path = path.with_extension("abi");
+    let abi = if abi_path.is_file() {
+        let json = serde_json::from_str(&fs::read_to_string(abi_path)?)?;
+        Some(ContractAbi::from_json(json)?)
+    } else {
+        None
+    };
+    Ok(Artifact { code, runtime, abi })
+}
+
+
+/// Load a truffle or hardhat JSON artifact.
+fn load_json(path: &Path, name: &str) -> Result<Artifact,Error> {
+    let artifact: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if let Some(contract_name) = artifact.get("contractName").and_then(Value::as_str) {
+        if contract_name != name {
+            warn!("artifact {:?} contains `{}` (expected `{}`)",path,contract_name,name);
+        }
+    }
+    let object = artifact.get("bytecode").and_then(Value::as_str)
+        .or_else(|| artifact.get("unlinked_binary").and_then(Value::as_str))
+        .or_else(|| artifact.pointer("/evm/bytecode/object").and_then(Value::as_str))
+        .ok_or_else(|| Error::message(format!("no bytecode found in {:?}",path)))?;
+    let code = load_bytecode(object,artifact.get("linkReferences"))?;
+    let runtime_object = artifact.get("deployedBytecode").and_then(Value::as_str)
+        .or_else(|| artifact.pointer("/evm/deployedBytecode/object").and_then(Value::as_str));
+    let runtime = match runtime_object {
+        Some(object) if !object.trim_left_matches("0x").is_empty() => {
+            Some(load_bytecode(object,artifact.get("deployedLinkReferences"))?)
+        },
+        _ => None,
+    };
+    let abi = match artifact.get("abi") {
+        Some(abi) => Some(ContractAbi::from_json(abi.clone())?),
+        None => None,
+    };
+    Ok(Artifact { code, runtime, abi })
+}
+
+
+/// Load bytecode, using explicit link references if the artifact provides them.
+fn load_bytecode(object: &str, link_references: Option<&Value>) -> Result<Bytecode,Error> {
+    match link_references {
+        Some(refs) if refs.as_object().map(|r| !r.is_empty()).unwrap_or(false) => {
+            Bytecode::from_standard_json(object,Some(refs))
+        },
+        _ => Bytecode::from_object(object),
+    }
+}
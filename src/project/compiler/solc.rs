@@ -0,0 +1,287 @@
+//! Compilation via the `--standard-json` interface of `solc`.
+use project::compiler::{self,Compiler,CompileContext,Artifact,Bytecode,Cache,ContractAbi};
+use project::contract::ContractConfig;
+use types::Error;
+use semver::{Version,VersionReq};
+use serde_json::{self,Value};
+use std::process::{Command,Stdio};
+use std::path::{Path,PathBuf};
+use std::io::Write;
+use std::fs;
+
+
+/// Default name of the `solc` executable
+pub const DEFAULT_BINARY: &str = "solc";
+
+
+/// Solidity compiler backend
+pub struct Solidity;
+
+
+impl Compiler for Solidity {
+
+    fn compile(&self, ctx: &CompileContext, config: &ContractConfig, source: &str) -> Result<Artifact,Error> {
+        let solc = ctx.solc.merged(config.solc.as_ref());
+        solc.compile(&ctx.scratch_dir,source,config.contract_name(),&ctx.cache)
+    }
+}
+
+
+/// Solidity compiler settings.
+///
+/// Used both for the project-wide `[solc]` section and for per-contract
+/// overrides; fields set in an override replace the project-wide value.
+///
+/// ```toml
+/// [solc]
+/// version = "^0.4.24"
+/// optimizer-runs = 200
+/// evm-version = "byzantium"
+/// remappings = ["token/=node_modules/token/"]
+/// source-dirs = ["lib"]
+/// ```
+///
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct SolcConfig {
+    /// Path to the `solc` binary
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// Required compiler version range (e.g. `^0.4.24`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Enable the optimizer with the specified number of runs
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub optimizer_runs: Option<u32>,
+
+    /// Target EVM version (e.g. `byzantium`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub evm_version: Option<String>,
+
+    /// Import remappings (relative targets are resolved from the project directory)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub remappings: Option<Vec<String>>,
+
+    /// Additional directories (relative to the project directory) from which sources may be
+    /// imported, by their path as given (e.g. `import "lib/Foo.sol";`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub source_dirs: Option<Vec<PathBuf>>,
+}
+
+
+impl SolcConfig {
+
+    /// Apply per-contract overrides on top of these settings.
+    pub fn merged(&self, overrides: Option<&SolcConfig>) -> SolcConfig {
+        let mut merged = self.clone();
+        if let Some(other) = overrides {
+            if other.path.is_some() { merged.path = other.path.clone(); }
+            if other.version.is_some() { merged.version = other.version.clone(); }
+            if other.optimizer_runs.is_some() { merged.optimizer_runs = other.optimizer_runs; }
+            if other.evm_version.is_some() { merged.evm_version = other.evm_version.clone(); }
+            if other.remappings.is_some() { merged.remappings = other.remappings.clone(); }
+            if other.source_dirs.is_some() { merged.source_dirs = other.source_dirs.clone(); }
+        }
+        merged
+    }
+
+    /// Path of the compiler executable.
+    pub fn binary(&self) -> &Path {
+        self.path.as_ref().map(PathBuf::as_path).unwrap_or_else(|| Path::new(DEFAULT_BINARY))
+    }
+
+    /// Query the version of the configured compiler.
+    pub fn compiler_version(&self) -> Result<Version,Error> {
+        let output = Command::new(compiler::resolve_binary(self.binary())?).arg("--version").output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let version = stdout.lines()
+            .filter_map(|line| line.trim().splitn(2,"Version:").nth(1))
+            .map(|version| version.trim().split('+').next().unwrap_or(""))
+            .next();
+        match version {
+            Some(version) => Ok(Version::parse(version)?),
+            None => {
+                let msg = format!("unable to determine version of `{}`",self.binary().display());
+                Err(Error::message(msg))
+            }
+        }
+    }
+
+    /// Ensure that the compiler satisfies the configured version requirement.
+    pub fn check_version(&self) -> Result<Version,Error> {
+        let version = self.compiler_version()?;
+        if let Some(range) = self.version.as_ref() {
+            let required = VersionReq::parse(range)?;
+            if !required.matches(&version) {
+                let msg = format!("solc version {} does not satisfy `{}`",version,range);
+                return Err(Error::message(msg));
+            }
+        }
+        Ok(version)
+    }
+
+    /// Build the standard-json input for compiling a single source file.
+    pub fn standard_input(&self, filename: &str, source: &str) -> Value {
+        let mut settings = json!({
+            "optimizer": {
+                "enabled": self.optimizer_runs.is_some(),
+                "runs": self.optimizer_runs.unwrap_or(200),
+            },
+            "outputSelection": {
+                "*": { "*": [
+                    "abi",
+                    "evm.bytecode.object",
+                    "evm.bytecode.linkReferences",
+                    "evm.deployedBytecode.object",
+                    "evm.deployedBytecode.linkReferences",
+                ] }
+            }
+        });
+        if let Some(evm_version) = self.evm_version.as_ref() {
+            settings["evmVersion"] = evm_version.as_str().into();
+        }
+        let remappings = self.resolved_remappings();
+        if !remappings.is_empty() {
+            settings["remappings"] = json!(remappings);
+        }
+        let mut sources = serde_json::Map::new();
+        sources.insert(filename.to_owned(),json!({ "content": source }));
+        json!({
+            "language": "Solidity",
+            "sources": sources,
+            "settings": settings,
+        })
+    }
+
+    /// Compile contract `name` from `filename` (relative to `dir`).
+    ///
+    /// The returned bytecode may still contain library placeholders.
+    pub fn compile(&self, dir: &Path, filename: &str, name: &str, cache: &Cache) -> Result<Artifact,Error> {
+        let version = self.check_version()?;
+        let source = fs::read_to_string(dir.join(filename))?;
+        let input = self.standard_input(filename,&source);
+        let key = Cache::key(&["solc",&version.to_string(),filename,name,&input.to_string()]);
+        if let Some(contract) = cache.load(&key,dir) {
+            debug!("using cached build of `{}`",name);
+            return parse_contract(&contract,name);
+        }
+        info!("compiling `{}` with solc {}",name,version);
+        let output = self.run(dir,&input)?;
+        check_errors(&output,filename)?;
+        let contract = output.get("contracts")
+            .and_then(|c| c.get(filename))
+            .and_then(|f| f.get(name))
+            .ok_or_else(|| Error::message(format!("no contract named `{}` in `{}`",name,filename)))?;
+        let artifact = parse_contract(contract,name)?;
+        let sources = output.get("sources").and_then(Value::as_object)
+            .into_iter().flat_map(|s| s.keys()).map(String::as_str);
+        if let Err(err) = cache.store(&key,dir,sources,contract) {
+            warn!("unable to cache build of `{}`: {}",name,err);
+        }
+        Ok(artifact)
+    }
+
+    /// Remappings with relative targets converted to absolute paths, followed by a
+    /// remapping of each source dir (as sources are compiled from a scratch directory).
+    fn resolved_remappings(&self) -> Vec<String> {
+        let source_dirs = self.source_dirs.iter().flat_map(|d| d.iter()).map(|dir| {
+            let prefix = dir.to_string_lossy().trim_left_matches("./").trim_right_matches('/').to_owned();
+            let target = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_owned());
+            format!("{}/={}/",prefix,target.display())
+        });
+        self.remappings.iter().flat_map(|r| r.iter()).map(|remapping| {
+            let mut split = remapping.rsplitn(2,'=');
+            match (split.next(),split.next()) {
+                (Some(target),Some(prefix)) if Path::new(target).is_relative() => {
+                    match fs::canonicalize(target) {
+                        Ok(resolved) => {
+                            let suffix = if target.ends_with('/') { "/" } else { "" };
+                            format!("{}={}{}",prefix,resolved.display(),suffix)
+                        },
+                        Err(_) => remapping.to_owned(),
+                    }
+                },
+                _ => remapping.to_owned(),
+            }
+        }).chain(source_dirs).collect()
+    }
+
+    /// Directories outside of the scratch directory which solc may read from.
+    fn allowed_paths(&self) -> Vec<String> {
+        let source_dirs = self.source_dirs.iter().flat_map(|d| d.iter())
+            .map(|d| fs::canonicalize(d).unwrap_or_else(|_| d.to_owned()))
+            .map(|d| d.to_string_lossy().into_owned());
+        let remap_targets = self.resolved_remappings().into_iter()
+            .filter_map(|r| r.rsplitn(2,'=').next().map(str::to_owned))
+            .filter(|target| Path::new(target).is_absolute());
+        source_dirs.chain(remap_targets).collect()
+    }
+
+    /// Run the compiler in standard-json mode.
+    fn run(&self, dir: &Path, input: &Value) -> Result<Value,Error> {
+        let mut command = Command::new(compiler::resolve_binary(self.binary())?);
+        command.arg("--standard-json")
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let allowed = self.allowed_paths();
+        if !allowed.is_empty() {
+            command.arg("--allow-paths").arg(allowed.join(","));
+        }
+        let mut child = command.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.to_string().as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        match serde_json::from_slice(&output.stdout) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                let msg = format!("solc failed: {}",String::from_utf8_lossy(&output.stderr).trim());
+                Err(Error::message(msg))
+            }
+        }
+    }
+}
+
+
+/// Surface any compiler errors contained in standard-json output.
+fn check_errors(output: &Value, filename: &str) -> Result<(),Error> {
+    let mut errors = Vec::new();
+    for error in output.get("errors").and_then(Value::as_array).into_iter().flat_map(|e| e.iter()) {
+        let message = error.get("formattedMessage").or_else(|| error.get("message"))
+            .and_then(Value::as_str).unwrap_or("unknown error").trim();
+        match error.get("severity").and_then(Value::as_str) {
+            Some("error") => errors.push(message.to_owned()),
+            _ => warn!("solc: {}",message),
+        }
+    }
+    if !errors.is_empty() {
+        let msg = format!("compilation failed for `{}`:\n{}",filename,errors.join("\n"));
+        Err(Error::message(msg))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// Extract the artifact of a single contract from standard-json output.
+fn parse_contract(contract: &Value, name: &str) -> Result<Artifact,Error> {
+    let code = contract.pointer("/evm/bytecode/object").and_then(Value::as_str).unwrap_or("");
+    if code.is_empty() {
+        let msg = format!("no bytecode generated for `{}` (abstract contract or interface?)",name);
+        return Err(Error::message(msg));
+    }
+    let code = Bytecode::from_standard_json(code,contract.pointer("/evm/bytecode/linkReferences"))?;
+    let runtime = match contract.pointer("/evm/deployedBytecode/object").and_then(Value::as_str) {
+        Some(object) if !object.is_empty() => {
+            let references = contract.pointer("/evm/deployedBytecode/linkReferences");
+            Some(Bytecode::from_standard_json(object,references)?)
+        },
+        _ => None,
+    };
+    let abi = ContractAbi::from_json(contract.get("abi").cloned().unwrap_or(Value::Null))?;
+    Ok(Artifact { code, runtime, abi: Some(abi) })
+}
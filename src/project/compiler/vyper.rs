@@ -0,0 +1,103 @@
+//! Compilation of Vyper sources.
+use project::compiler::{self,Compiler,CompileContext,Artifact,Bytecode,Cache,ContractAbi};
+use project::contract::ContractConfig;
+use types::Error;
+use serde_json::{self,Value};
+use std::process::Command;
+use std::path::{Path,PathBuf};
+use std::fs;
+
+
+/// Default name of the `vyper` executable
+pub const DEFAULT_BINARY: &str = "vyper";
+
+
+/// Vyper compiler backend
+pub struct Vyper;
+
+
+impl Compiler for Vyper {
+
+    fn compile(&self, ctx: &CompileContext, _config: &ContractConfig, source: &str) -> Result<Artifact,Error> {
+        ctx.vyper.compile(&ctx.scratch_dir,source,&ctx.cache)
+    }
+}
+
+
+/// Vyper compiler settings (`[vyper]` section)
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct VyperConfig {
+    /// Path to the `vyper` binary
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+
+impl VyperConfig {
+
+    /// Path of the compiler executable.
+    pub fn binary(&self) -> &Path {
+        self.path.as_ref().map(PathBuf::as_path).unwrap_or_else(|| Path::new(DEFAULT_BINARY))
+    }
+
+    /// Query the version of the configured compiler.
+    pub fn compiler_version(&self) -> Result<String,Error> {
+        let output = Command::new(compiler::resolve_binary(self.binary())?).arg("--version").output()?;
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+        if output.status.success() && !version.is_empty() {
+            Ok(version)
+        } else {
+            let msg = format!("unable to determine version of `{}`",self.binary().display());
+            Err(Error::message(msg))
+        }
+    }
+
+    /// Compile `filename` (relative to `dir`).
+    pub fn compile(&self, dir: &Path, filename: &str, cache: &Cache) -> Result<Artifact,Error> {
+        let version = self.compiler_version()?;
+        let source = fs::read_to_string(dir.join(filename))?;
+        let key = Cache::key(&["vyper",&version,"bytecode,bytecode_runtime,abi",filename,&source]);
+        if let Some(output) = cache.load(&key,dir) {
+            debug!("using cached build of `{}`",filename);
+            return parse_output(&output);
+        }
+        info!("compiling `{}` with vyper {}",filename,version);
+        let bytecode = self.run(dir,filename,"bytecode")?;
+        let runtime = self.run(dir,filename,"bytecode_runtime")?;
+        let abi: Value = serde_json::from_str(&self.run(dir,filename,"abi")?)?;
+        let output = json!({ "bytecode": bytecode.trim(), "runtime": runtime.trim(), "abi": abi });
+        let artifact = parse_output(&output)?;
+        if let Err(err) = cache.store(&key,dir,Some(filename),&output) {
+            warn!("unable to cache build of `{}`: {}",filename,err);
+        }
+        Ok(artifact)
+    }
+
+    /// Run the compiler, producing output of the specified format.
+    fn run(&self, dir: &Path, filename: &str, format: &str) -> Result<String,Error> {
+        let output = Command::new(compiler::resolve_binary(self.binary())?)
+            .arg("-f").arg(format)
+            .arg(filename)
+            .current_dir(dir)
+            .output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            let msg = format!("compilation failed for `{}`:\n{}",filename,String::from_utf8_lossy(&output.stderr).trim());
+            Err(Error::message(msg))
+        }
+    }
+}
+
+
+fn parse_output(output: &Value) -> Result<Artifact,Error> {
+    let object = output.get("bytecode").and_then(Value::as_str).unwrap_or("");
+    let code = Bytecode::from_object(object)?;
+    let runtime = match output.get("runtime").and_then(Value::as_str) {
+        Some(object) if !object.is_empty() => Some(Bytecode::from_object(object)?),
+        _ => None,
+    };
+    let abi = ContractAbi::from_json(output.get("abi").cloned().unwrap_or(Value::Null))?;
+    Ok(Artifact { code, runtime, abi: Some(abi) })
+}
@@ -1,16 +1,17 @@
 use mimir_crypto::secp256k1::Address;
 use mimir_types::Bytes;
 use project::Project;
+use project::compiler::{SolcConfig,CompilerKind,Bytecode,Libraries,CompileContext,ContractAbi};
+use project::storage::Storage;
 use types::{Tags,Error};
 use util;
 use ethabi::{Param,ParamType,Constructor,Token};
+use ethabi::token::{Tokenizer,LenientTokenizer};
 use serde::de::{self,Deserialize,Deserializer};
 use serde::ser::{Serialize,Serializer};
 use toml;
-use std::process::Command;
 use std::str::FromStr;
-use std::path::Path;
-use std::{fs,fmt};
+use std::fmt;
 
 
 /// Source of example/debug contract
@@ -39,6 +40,8 @@ pub enum ContractArgument {
     Address(Address),
     /// Arbitrary file contents
     Include(String),
+    /// Literal value, parsed according to the parameter type (requires an ABI)
+    Literal(String),
 }
 
 
@@ -82,6 +85,9 @@ impl FromStr for ContractArgument {
                     (Some("include"),Some(filename)) => {
                         Ok(ContractArgument::Include(filename.into()))
                     },
+                    (Some("literal"),Some(value)) => {
+                        Ok(ContractArgument::Literal(value.into()))
+                    },
                     _=> {
                         let message = format!("unknown contract argument `{}`",s.trim());
                         Err(Error::message(message))
@@ -114,6 +120,9 @@ impl fmt::Display for ContractArgument {
             ContractArgument::Include(file) => {
                 write!(f,"include::{}",file)
             },
+            ContractArgument::Literal(value) => {
+                write!(f,"literal::{}",value)
+            },
         }
     }
 }
@@ -121,57 +130,58 @@ impl fmt::Display for ContractArgument {
 
 impl ContractArgument {
 
-    fn build_with(&self, project: &Project) -> Result<(Param,Token),Error> {
+    /// Resolve the address(es) represented by this argument.
+    pub fn resolve_addrs(&self, project: &Project) -> Result<Vec<Address>,Error> {
         match self {
             ContractArgument::AuthorityAddrs => {
-                let param_type = ParamType::Array(Box::new(ParamType::Address));
-                let param = Param { name: "authority_addrs".into(), kind: param_type };
-                let authority_addrs: Vec<Token> = project.iter_nodes().filter_map(|node| {
-                    if node.is_authority() {
-                        Some(Token::Address(node.account_addr().into_inner().into()))
-                    } else {
-                        None
-                    }
-                }).collect();
-                let token = Token::Array(authority_addrs);
-                Ok((param,token))
+                let authority_addrs = project.iter_nodes()
+                    .filter(|node| node.is_authority())
+                    .map(|node| node.account_addr())
+                    .collect();
+                Ok(authority_addrs)
             },
             ContractArgument::MatchAddrs(tag) => {
-                let param_type = ParamType::Array(Box::new(ParamType::Address));
-                let param = Param { name: tag.to_owned(), kind: param_type }; 
-                let matches: Vec<Token> = project.iter_accounts().filter_map(|account| {
-                    if account.tags().contains(tag) {
-                        Some(Token::Address(account.address().into_inner().into()))
-                    } else {
-                        None
-                    }
-                }).collect();
-                let token = Token::Array(matches);
-                Ok((param,token))
+                let matches = project.iter_accounts()
+                    .filter(|account| account.tags().contains(tag))
+                    .map(|account| account.address())
+                    .collect();
+                Ok(matches)
             },
             ContractArgument::AccountAddr(name) => {
-                let param_type = ParamType::Address;
-                let param = Param { name: name.to_owned(), kind: param_type };
                 if let Some(account) = project.iter_accounts().find(|a| a.name() == name) {
-                    let token = Token::Address(account.address().into_inner().into());
-                    Ok((param,token))
+                    Ok(vec![account.address()])
                 } else {
                     let message = format!("unable to locate include address of `{}` (not found)",name);
                     Err(Error::message(message))
                 }
             },
-            ContractArgument::Address(address) => {
-                let param_type = ParamType::Address;
-                let param = Param { name: "address".into(), kind: param_type };
-                let token = Token::Address(address.into_inner().into());
-                Ok((param,token))
+            ContractArgument::Address(address) => Ok(vec![*address]),
+            other => {
+                let message = format!("argument `{}` does not represent an address",other);
+                Err(Error::message(message))
+            },
+        }
+    }
+
+    /// Build the token represented by this argument.
+    fn build_with(&self, project: &Project) -> Result<Token,Error> {
+        let address_token = |addr: Address| Token::Address(addr.into_inner().into());
+        match self {
+            ContractArgument::Literal(value) => {
+                let message = format!("literal argument `{}` requires an ABI",value);
+                Err(Error::message(message))
+            },
+            ContractArgument::AuthorityAddrs | ContractArgument::MatchAddrs(_) => {
+                let addrs = self.resolve_addrs(project)?;
+                Ok(Token::Array(addrs.into_iter().map(address_token).collect()))
+            },
+            ContractArgument::AccountAddr(_) | ContractArgument::Address(_) => {
+                let addrs = self.resolve_addrs(project)?;
+                Ok(address_token(addrs[0]))
             },
             ContractArgument::Include(filename) => {
                 if let Some(buffer) = project.get_template(&filename) {
-                    let param_type = ParamType::String;
-                    let param = Param { name: "include".into(), kind: param_type };
-                    let token = Token::String(buffer.to_owned());
-                Ok((param,token))
+                    Ok(Token::String(buffer.to_owned()))
                 } else {
                     let message = format!("unable to include `{}` (not found)",filename);
                     Err(Error::message(message))
@@ -179,23 +189,87 @@ impl ContractArgument {
             }
         }
     }
+
+    /// Parameter type inferred from the argument kind (used when no ABI is available).
+    fn infer_param(&self) -> Param {
+        let (name,kind) = match self {
+            ContractArgument::AuthorityAddrs => {
+                ("authority_addrs".to_owned(),ParamType::Array(Box::new(ParamType::Address)))
+            },
+            ContractArgument::MatchAddrs(tag) => {
+                (tag.to_owned(),ParamType::Array(Box::new(ParamType::Address)))
+            },
+            ContractArgument::AccountAddr(name) => (name.to_owned(),ParamType::Address),
+            ContractArgument::Address(_) => ("address".to_owned(),ParamType::Address),
+            ContractArgument::Include(_) => ("include".to_owned(),ParamType::String),
+            ContractArgument::Literal(_) => ("literal".to_owned(),ParamType::String),
+        };
+        Param { name, kind }
+    }
 }
 
 
-/// encode contract arguments
-fn encode_arguments(project: &Project, args: &[ContractArgument], code: Bytes) -> Result<Bytes,Error> {
-    if !args.is_empty() {
-        let (mut params, mut tokens) = (Vec::new(),Vec::new());
-        for argument in args.iter() {
-            let (param,token) = argument.build_with(project)?;
-            params.push(param);
-            tokens.push(token);
-        }
-        let constructor = Constructor { inputs: params };
-        let encoded = constructor.encode_input(code.into_inner(),&tokens)?;
-        Ok(Bytes::from(encoded))
-    } else {
-        Ok(code)
+/// Build the tokens for `args`, checking them against `expected` if the ABI is known.
+///
+/// Returns the parameters used for encoding alongside the tokens themselves.
+pub fn build_tokens(owner: &str, args: &[ContractArgument], expected: Option<&[Param]>, project: &Project) -> Result<(Vec<Param>,Vec<Token>),Error> {
+    let (mut params, mut tokens) = (Vec::new(),Vec::new());
+    match expected {
+        Some(expected) => {
+            for (argument,param) in args.iter().zip(expected.iter()) {
+                let token = match argument {
+                    ContractArgument::Literal(value) => {
+                        LenientTokenizer::tokenize(&param.kind,value).ok()
+                    },
+                    other => coerce_token(other.build_with(project)?,&param.kind),
+                };
+                if let Some(token) = token {
+                    params.push(param.clone());
+                    tokens.push(token);
+                } else {
+                    let message = format!("invalid argument `{}` for parameter `{}` of `{}` (expected `{}`)",
+                        argument,param.name,owner,param.kind);
+                    return Err(Error::message(message));
+                }
+            }
+        },
+        None => {
+            warn!("no ABI available for `{}`; argument types are inferred",owner);
+            for argument in args.iter() {
+                tokens.push(argument.build_with(project)?);
+                params.push(argument.infer_param());
+            }
+        },
+    }
+    Ok((params,tokens))
+}
+
+
+/// Attempt to fit a token to the expected parameter type, converting between
+/// compatible representations (e.g. dynamic to fixed-size arrays) if needed.
+fn coerce_token(token: Token, kind: &ParamType) -> Option<Token> {
+    if token.type_check(kind) {
+        return Some(token);
+    }
+    match (token,kind) {
+        (Token::Array(tokens),ParamType::FixedArray(inner,size)) => {
+            if tokens.len() == *size {
+                let coerced = tokens.into_iter()
+                    .map(|t| coerce_token(t,inner))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Token::FixedArray(coerced))
+            } else {
+                None
+            }
+        },
+        (Token::Array(tokens),ParamType::Array(inner)) => {
+            let coerced = tokens.into_iter()
+                .map(|t| coerce_token(t,inner))
+                .collect::<Option<Vec<_>>>()?;
+            Some(Token::Array(coerced))
+        },
+        (Token::String(string),ParamType::Bytes) => Some(Token::Bytes(string.into_bytes())),
+        _ => None,
     }
 }
 
@@ -206,9 +280,15 @@ pub struct Contract {
     pub name: String,
     pub addr: Address,
     pub code: Bytes,
+    /// Runtime code (if known)
+    pub runtime: Option<Bytes>,
+    pub abi: Option<ContractAbi>,
     pub args: Vec<ContractArgument>,
     pub role: Option<ContractRole>,
     pub tags: Tags,
+    /// Pre-computed storage; if set, the contract is inserted at genesis with
+    /// its runtime code & this storage instead of running its constructor.
+    pub storage: Option<Storage>,
 }
 
 
@@ -218,12 +298,42 @@ impl Contract {
     ///
     pub fn seed_args(&self, project: &Project) -> Result<Option<Bytes>,Error> {
         if !self.args.is_empty() {
-            let encoded = encode_arguments(project,&self.args,self.code.clone())?;
+            let encoded = self.encode_arguments(project)?;
             Ok(Some(encoded))
         } else {
+            self.check_arg_count()?;
             Ok(None)
         }
     }
+
+    /// Constructor parameters as declared by the contract ABI (if known).
+    fn constructor_params(&self) -> Option<&[Param]> {
+        self.abi.as_ref().map(|abi| {
+            abi.constructor.as_ref()
+                .map(|c| c.inputs.as_slice())
+                .unwrap_or(&[])
+        })
+    }
+
+    fn check_arg_count(&self) -> Result<(),Error> {
+        match self.constructor_params() {
+            Some(params) if params.len() != self.args.len() => {
+                let message = format!("contract `{}` expects {} constructor argument(s) (got {})",
+                    self.name,params.len(),self.args.len());
+                Err(Error::message(message))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Encode constructor arguments, checking them against the ABI if one is available.
+    fn encode_arguments(&self, project: &Project) -> Result<Bytes,Error> {
+        self.check_arg_count()?;
+        let (params,tokens) = build_tokens(&self.name,&self.args,self.constructor_params(),project)?;
+        let constructor = Constructor { inputs: params };
+        let encoded = constructor.encode_input(self.code.clone().into_inner(),&tokens)?;
+        Ok(Bytes::from(encoded))
+    }
 }
 
 
@@ -239,6 +349,18 @@ pub struct ContractConfig {
     /// Constructor code (loaded from `contracts/{name}` if unspecified)
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub code: Option<Bytes>,
+
+    /// Source file containing the contract (defaults to `{name}.sol` or `{name}.vy`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Name of the contract within its source (defaults to `name`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+
+    /// Compiler backend (inferred from the source file extension if unspecified)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerKind>,
     
     /// Arguments to be seeded (if any)
     #[serde(default)]
@@ -251,67 +373,60 @@ pub struct ContractConfig {
     /// Arbitrary tags
     #[serde(default)]
     pub tags: Tags,
+
+    /// Contract-specific compiler settings (override `[solc]`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub solc: Option<SolcConfig>,
 }
 
 
 impl ContractConfig {
 
     pub fn new(name: String, addr: Address) -> Self {
-        let (code,args,role,tags) = Default::default();
-        Self { name, addr, code, args, role, tags }
+        let (code,source,contract,compiler,args,role,tags,solc) = Default::default();
+        Self { name, addr, code, source, contract, compiler, args, role, tags, solc }
+    }
+
+    /// Name of the contract within its source.
+    pub fn contract_name(&self) -> &str {
+        self.contract.as_ref().unwrap_or(&self.name)
+    }
+
+    /// Name of the source file containing this contract.
+    pub fn source_file(&self) -> String {
+        self.source.clone().unwrap_or_else(|| format!("{}.sol",self.name))
     }
 
-    pub fn load_contract(&self, contract_dir: impl AsRef<Path>, no_solc: bool) -> Result<Contract,Error> {
+    pub fn load_contract(&self, ctx: &CompileContext) -> Result<Contract,Error> {
         util::check_name(&self.name)?;
         let name = self.name.to_owned();
-        let code: Bytes = match self.code.as_ref() {
-            Some(code) => code.to_owned(),
+        let (code,runtime,abi): (Bytes,Option<Bytes>,Option<ContractAbi>) = match self.code.as_ref() {
+            Some(code) => (code.to_owned(),None,None),
             None => {
-                let mut path = contract_dir.as_ref().join(&name);
-                if !no_solc {
-                    path.set_extension("sol");
-                    if path.is_file() {
-                        debug!("compiling contract {:?}",path);
-                        let exit_status = {
-                            let file = path.file_name().expect("name must exist if file exists");
-                            let dir = path.parent().unwrap_or(".".as_ref());
-                            Command::new("solc")
-                                .arg("-o").arg(".")
-                                .arg("--abi").arg("--bin")
-                                .arg("--overwrite")
-                                .arg(&file)
-                                .current_dir(dir)
-                                .status()?
-                        };
-                        if exit_status.success() {
-                            path.set_extension("bin");
-                            fs::read_to_string(&path)?.parse()?
-                        } else {
-                            let msg = format!("compilation failed for `{}`",path.to_string_lossy());
-                            return Err(Error::message(msg));
-                        }
-                    } else {
-                        let msg = format!("unable to locate `{}.sol`",name);
-                        return Err(Error::message(msg));
-                    }
-                } else {
-                    path.set_extension("bin");
-                    if path.is_file() {
-                        debug!("loading existing binary {:?}",path);
-                        fs::read_to_string(&path)?.parse()?
-                    } else {
-                        let msg = format!("unable to locate `{}.bin`",name);
-                        return Err(Error::message(msg));
-                    }
-                }
+                let (kind,source) = ctx.resolve(self)?;
+                let artifact = kind.backend().compile(ctx,self,&source)?;
+                let runtime = match artifact.runtime.as_ref() {
+                    Some(runtime) => Some(link(&name,runtime,&ctx.libraries)?),
+                    None => None,
+                };
+                (link(&name,&artifact.code,&ctx.libraries)?,runtime,artifact.abi)
             }
         };
         let (addr,role,args,tags) = (self.addr,self.role,self.args.clone(),self.tags.clone());
-        Ok(Contract { name, addr, code, args, role, tags })
+        let storage = None;
+        Ok(Contract { name, addr, code, runtime, abi, args, role, tags, storage })
     }
 }
 
 
+/// Link library placeholders, noting the contract name on failure.
+fn link(name: &str, bytecode: &Bytecode, libraries: &Libraries) -> Result<Bytes,Error> {
+    bytecode.link(libraries).map_err(|err| {
+        Error::message(format!("failed to link `{}`: {}",name,err))
+    })
+}
+
+
 impl FromStr for ContractConfig {
 
     type Err = Error;
@@ -354,7 +469,7 @@ impl ValidatorContract {
 }
 
 /// Role of system contract (e.g. transaction permissioning).
-#[derive(Debug,Copy,Clone,Serialize,Deserialize)]
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum SystemContract {
     /// Equivalent to `transactionPermissionContract`
@@ -378,3 +493,82 @@ impl SystemContract {
 
 
 
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_types::Bytes;
+    use project::compiler::ContractAbi;
+    use project::tests::project;
+    use serde_json::Value;
+    use util;
+    use super::*;
+
+    /// Constructor code of the test contract (`PUSH1 0x00`).
+    const CODE: &[u8] = &[0x60,0x00];
+
+    fn contract(abi: Option<Value>, args: &[&str]) -> Contract {
+        Contract {
+            name: "Example".to_owned(),
+            addr: "0x0000000000000000000000000000000000003c40".parse().unwrap(),
+            code: Bytes::from(CODE.to_vec()),
+            runtime: None,
+            abi: abi.map(|json| ContractAbi::from_json(json).unwrap()),
+            args: args.iter().map(|arg| arg.parse().unwrap()).collect(),
+            role: None,
+            tags: Default::default(),
+            storage: None,
+        }
+    }
+
+    /// ABI of `constructor(address owner, uint256 supply)`.
+    fn owner_supply_abi() -> Value {
+        json!([{
+            "type": "constructor",
+            "inputs": [{"name": "owner","type": "address"},{"name": "supply","type": "uint256"}],
+            "payable": false,
+            "stateMutability": "nonpayable"
+        }])
+    }
+
+    fn encode(contract: &Contract) -> Result<String,Error> {
+        let encoded = contract.encode_arguments(&project("","",""))?;
+        Ok(util::hex_string(&encoded.into_inner()))
+    }
+
+    #[test]
+    fn arg_count_checked_against_abi() {
+        let owner = "address::00000000000000000000000000000000000a11ce";
+        assert!(contract(Some(owner_supply_abi()),&[owner,"literal::1"]).check_arg_count().is_ok());
+        let err = contract(Some(owner_supply_abi()),&[owner]).check_arg_count().unwrap_err();
+        assert!(err.to_string().contains("expects 2 constructor argument(s) (got 1)"),"unexpected error: {}",err);
+        // without a constructor, no arguments are expected.
+        assert!(contract(Some(json!([])),&[]).check_arg_count().is_ok());
+        assert!(contract(Some(json!([])),&[owner]).check_arg_count().is_err());
+        // without an ABI, the count cannot be checked.
+        assert!(contract(None,&[owner,"literal::1"]).check_arg_count().is_ok());
+    }
+
+    #[test]
+    fn arguments_appended_to_code() {
+        let owner = "address::00000000000000000000000000000000000a11ce";
+        let encoded = encode(&contract(Some(owner_supply_abi()),&[owner,"literal::1000"])).unwrap();
+        let expected = format!("6000{:0>64}{:064x}","a11ce",1000);
+        assert_eq!(encoded,expected);
+        // without an ABI, address arguments are inferred.
+        let encoded = encode(&contract(None,&[owner])).unwrap();
+        assert_eq!(encoded,format!("6000{:0>64}","a11ce"));
+    }
+
+    #[test]
+    fn invalid_arguments_rejected() {
+        let owner = "address::00000000000000000000000000000000000a11ce";
+        let err = encode(&contract(Some(owner_supply_abi()),&[owner,"literal::lots"])).unwrap_err();
+        assert!(err.to_string().contains("parameter `supply`"),"unexpected error: {}",err);
+        let err = encode(&contract(Some(owner_supply_abi()),&["literal::1",owner])).unwrap_err();
+        assert!(err.to_string().contains("parameter `owner`"),"unexpected error: {}",err);
+        let err = encode(&contract(None,&["literal::1"])).unwrap_err();
+        assert!(err.to_string().contains("requires an ABI"),"unexpected error: {}",err);
+        assert!(encode(&contract(Some(owner_supply_abi()),&[owner])).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Contracts deployed after genesis via offline-signed transactions.
+use mimir_crypto::secp256k1::Address;
+use project::Project;
+use project::contract::{self,Contract,ContractConfig,ContractArgument};
+use project::compiler::{CompilerKind,SolcConfig,CompileContext,Libraries};
+use project::actor::Actors;
+use project::storage::{self,Word};
+use types::{Transaction,SignedTransaction,Error,contract_address};
+use ethabi::Function;
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer};
+use std::collections::HashMap;
+
+
+/// Gas limit used by deployment transactions unless otherwise specified
+pub const DEFAULT_GAS: u64 = 3_000_000;
+
+
+/// A contract deployed by an actor after genesis
+///
+/// ```toml
+/// [[deployment]]
+/// name = "Registry"
+/// deployer = "actor-0"
+/// args = ["authority-addrs"]
+///
+/// [[deployment.call]]
+/// function = "register"
+/// args = ["literal::echo", "account-addr::EchoContract"]
+/// ```
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct DeploymentConfig {
+    /// Name of contract
+    pub name: String,
+
+    /// Name of the (internal) actor which signs the deployment
+    pub deployer: String,
+
+    /// Source file containing the contract (defaults to `{name}.sol` or `{name}.vy`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Compiler backend (inferred from the source file extension if unspecified)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerKind>,
+
+    /// Constructor arguments
+    #[serde(default)]
+    pub args: Vec<ContractArgument>,
+
+    /// Gas limit of the deployment transaction
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub gas: Option<u64>,
+
+    /// Gas price of all transactions of this deployment (defaults to zero)
+    #[serde(default)]
+    pub gas_price: u64,
+
+    /// Value transferred to the constructor
+    #[serde(default)]
+    pub value: Wei,
+
+    /// Functions to call once the contract is deployed (in order)
+    #[serde(rename = "call",default,skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallConfig>,
+
+    /// Contract-specific compiler settings (override `[solc]`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub solc: Option<SolcConfig>,
+}
+
+
+/// A function call made to a freshly deployed contract
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct CallConfig {
+    /// Name of function
+    pub function: String,
+
+    /// Function arguments
+    #[serde(default)]
+    pub args: Vec<ContractArgument>,
+
+    /// Gas limit of the call transaction
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub gas: Option<u64>,
+
+    /// Value transferred with the call
+    #[serde(default)]
+    pub value: Wei,
+}
+
+
+impl DeploymentConfig {
+
+    /// Number of transactions (and therefore nonces) used by this deployment.
+    fn tx_count(&self) -> u64 { 1 + self.calls.len() as u64 }
+
+    fn contract_config(&self, addr: Address) -> ContractConfig {
+        let mut config = ContractConfig::new(self.name.clone(),addr);
+        config.source = self.source.clone();
+        config.compiler = self.compiler;
+        config.args = self.args.clone();
+        config.solc = self.solc.clone();
+        config
+    }
+}
+
+
+/// An amount of wei, given as an integer or (beyond the range of toml integers) as a
+/// decimal string, e.g. `value = "100000000000000000000"` for 100 ether.
+#[derive(Default,Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Wei(pub Word);
+
+
+impl Serialize for Wei {
+
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok,S::Error> where S: Serializer {
+        serializer.serialize_str(&storage::format_uint(self.0))
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Wei {
+
+    fn deserialize<D>(deserializer: D) -> Result<Self,D::Error> where D: Deserializer<'de> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Int(u64),
+            Decimal(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Int(value) => Ok(Wei(storage::uint_word(value))),
+            Raw::Decimal(decimal) => storage::parse_uint(decimal.trim()).map(Wei).ok_or_else(|| {
+                de::Error::custom(format!("invalid amount of wei `{}`",decimal))
+            }),
+        }
+    }
+}
+
+
+/// A fully loaded deployment
+#[derive(Debug,Clone)]
+pub struct Deployment {
+    pub contract: Contract,
+    pub deployer: String,
+    /// Nonce of the deployment transaction (calls use consecutive nonces)
+    pub nonce: u64,
+    pub gas: u64,
+    pub gas_price: u64,
+    pub value: Wei,
+    pub calls: Vec<CallConfig>,
+}
+
+
+/// A signed transaction produced by a deployment
+#[derive(Debug,Clone)]
+pub struct DeploymentTx {
+    /// Human-readable description (e.g. `Registry.register`)
+    pub label: String,
+    pub nonce: u64,
+    pub signed: SignedTransaction,
+}
+
+
+impl Deployment {
+
+    /// Build & sign all transactions of this deployment.
+    pub fn sign(&self, project: &Project, chain_id: u64) -> Result<Vec<DeploymentTx>,Error> {
+        let signer = project.iter_actors().find(|actor| actor.name() == self.deployer)
+            .and_then(|actor| actor.internal())
+            .map(|actor| actor.signer())
+            .ok_or_else(|| Error::message(format!("deployer `{}` is not an internal actor",self.deployer)))?;
+        let code = self.contract.seed_args(project)?
+            .unwrap_or_else(|| self.contract.code.clone());
+        let create = Transaction {
+            nonce: self.nonce,
+            gas_price: self.gas_price,
+            gas: self.gas,
+            to: None,
+            value: self.value.0,
+            data: code.into_inner(),
+        };
+        let mut txs = vec![DeploymentTx {
+            label: self.contract.name.clone(),
+            nonce: self.nonce,
+            signed: create.sign(signer,chain_id)?,
+        }];
+        for (index,call) in self.calls.iter().enumerate() {
+            let nonce = self.nonce + 1 + index as u64;
+            let function = self.function(&call.function)?;
+            let label = format!("{}.{}",self.contract.name,call.function);
+            if function.inputs.len() != call.args.len() {
+                let message = format!("`{}` expects {} argument(s) (got {})",label,function.inputs.len(),call.args.len());
+                return Err(Error::message(message));
+            }
+            let (_,tokens) = contract::build_tokens(&label,&call.args,Some(function.inputs.as_slice()),project)?;
+            let transaction = Transaction {
+                nonce: nonce,
+                gas_price: self.gas_price,
+                gas: call.gas.unwrap_or(DEFAULT_GAS),
+                to: Some(self.contract.addr),
+                value: call.value.0,
+                data: function.encode_input(&tokens)?,
+            };
+            txs.push(DeploymentTx { label, nonce, signed: transaction.sign(signer,chain_id)? });
+        }
+        Ok(txs)
+    }
+
+    fn function(&self, name: &str) -> Result<&Function,Error> {
+        let abi = self.contract.abi.as_ref().ok_or_else(|| {
+            Error::message(format!("calling `{}` of `{}` requires an ABI",name,self.contract.name))
+        })?;
+        let function = abi.function(name)?;
+        Ok(function)
+    }
+}
+
+
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+pub struct DeploymentConfigs(Vec<DeploymentConfig>);
+
+
+impl DeploymentConfigs {
+
+    pub fn import(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Assign nonces & contract addresses to all deployments.
+    ///
+    /// Each deployer starts at nonce zero, and deployments are signed
+    /// in the order in which they are declared.
+    pub fn plan(&self, actors: &Actors) -> Result<Vec<(u64,Address)>,Error> {
+        let mut nonces: HashMap<&str,u64> = HashMap::new();
+        let mut plan = Vec::new();
+        for config in self.0.iter() {
+            let deployer = actors.iter().find(|actor| actor.name() == config.deployer)
+                .and_then(|actor| actor.internal())
+                .ok_or_else(|| {
+                    let msg = format!("deployer `{}` of `{}` must be an internal actor",config.deployer,config.name);
+                    Error::message(msg)
+                })?;
+            let nonce = nonces.entry(config.deployer.as_str()).or_insert(0);
+            let addr = contract_address(deployer.address(),*nonce)?;
+            plan.push((*nonce,addr));
+            *nonce += config.tx_count();
+        }
+        Ok(plan)
+    }
+
+    /// Addresses of all deployed contracts, available for library linking.
+    pub fn libraries(&self, plan: &[(u64,Address)], libraries: &mut Libraries) {
+        for (config,(_,addr)) in self.0.iter().zip(plan.iter()) {
+            let source = config.contract_config(*addr).source_file();
+            libraries.insert(&source,&config.name,*addr);
+        }
+    }
+
+    pub fn try_load(&self, plan: &[(u64,Address)], ctx: &CompileContext) -> Result<Vec<Deployment>,Error> {
+        self.0.iter().zip(plan.iter()).map(|(config,(nonce,addr))| {
+            let contract = config.contract_config(*addr).load_contract(ctx)?;
+            Ok(Deployment {
+                contract: contract,
+                deployer: config.deployer.clone(),
+                nonce: *nonce,
+                gas: config.gas.unwrap_or(DEFAULT_GAS),
+                gas_price: config.gas_price,
+                value: config.value,
+                calls: config.calls.clone(),
+            })
+        }).collect()
+    }
+}
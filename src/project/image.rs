@@ -0,0 +1,167 @@
+//! Docker image selection.
+//!
+use project::node::{Node,NodeRole};
+use semver::Version;
+use std::collections::BTreeMap;
+
+
+/// Repository of the default node image, whose tags follow parity's versions
+pub const DEFAULT_REPOSITORY: &'static str = "mimirbridge/pib-parity";
+
+
+/// Base image & parity version of the nodes
+///
+/// ```toml
+/// [docker.authority]
+/// version = "1.11.11"
+///
+/// [docker.tag.canary]
+/// image = "registry.example.com/pib-parity:2.0.8-rc"
+/// version = "2.0.8"
+///
+/// [docker.node.node-0]
+/// version = "2.0.8"
+/// ```
+///
+/// Settings of a node override those of its tags (applied in order of tag name), which
+/// override those of its role, which override those of `all`.
+///
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct DockerConfig {
+    /// Settings of every node
+    #[serde(default)]
+    pub all: ImageOptions,
+
+    /// Settings of authority nodes
+    #[serde(default)]
+    pub authority: ImageOptions,
+
+    /// Settings of interface nodes
+    #[serde(default)]
+    pub interface: ImageOptions,
+
+    /// Settings of tagged nodes, by tag
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag: BTreeMap<String,ImageOptions>,
+
+    /// Settings of individual nodes, by name
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub node: BTreeMap<String,ImageOptions>,
+}
+
+
+impl DockerConfig {
+
+    /// Combined settings of `node`.
+    pub fn options(&self, node: &Node) -> ImageOptions {
+        let mut options = self.all.clone();
+        match node.node_role() {
+            NodeRole::Authority => options.merge(&self.authority),
+            NodeRole::Interface => options.merge(&self.interface),
+        }
+        for (tag,tag_options) in self.tag.iter() {
+            if node.tags().contains(tag) {
+                options.merge(tag_options);
+            }
+        }
+        if let Some(node_options) = self.node.get(node.name()) {
+            options.merge(node_options);
+        }
+        options
+    }
+}
+
+
+/// Base image & parity version of a node
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct ImageOptions {
+    /// Base image (defaults to the image of the same `version` from `mimirbridge/pib-parity`);
+    /// must provide `parity`, `ethstore` & `bash`, as the generated `Dockerfile` runs `init.sh`,
+    /// as well as `curl` if the node is healthchecked or submits deployments
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+
+    /// Parity version of the image (e.g. `2.0.8` or `2.0`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+
+impl ImageOptions {
+
+    /// Override these settings with any settings of `other`.
+    pub fn merge(&mut self, other: &Self) {
+        if other.image.is_some() { self.image = other.image.clone(); }
+        if other.version.is_some() { self.version = other.version.clone(); }
+    }
+
+    /// Base image of the node (if other than the template's).
+    pub fn base_image(&self) -> Option<String> {
+        match (self.image.as_ref(),self.version.as_ref()) {
+            (Some(image),_) => Some(image.to_owned()),
+            (None,Some(version)) => Some(format!("{}:{}",DEFAULT_REPOSITORY,version.trim_left_matches('v'))),
+            (None,None) => None,
+        }
+    }
+
+    /// Parity version of the node (if specified).  Versions may omit the patch (or minor)
+    /// number; channel names such as `stable` are not versions, and yield `None`.
+    pub fn parity_version(&self) -> Option<Version> {
+        let version = self.version.as_ref()?.trim().trim_left_matches('v');
+        let parts = version.split('.').count();
+        let padded = match parts {
+            1 => format!("{}.0.0",version),
+            2 => format!("{}.0",version),
+            _ => version.to_owned(),
+        };
+        match Version::parse(&padded) {
+            Ok(version) => Some(version),
+            Err(_) => {
+                warn!("unable to interpret `{}` as a parity version; config options will not be adjusted",version);
+                None
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use super::ImageOptions;
+
+    fn options(image: Option<&str>, version: Option<&str>) -> ImageOptions {
+        ImageOptions { image: image.map(String::from), version: version.map(String::from) }
+    }
+
+    fn parity_version(version: &str) -> Option<Version> {
+        options(None,Some(version)).parity_version()
+    }
+
+    #[test]
+    fn full_versions() {
+        assert_eq!(parity_version("2.0.8"),Some(Version::new(2,0,8)));
+        assert_eq!(parity_version("v1.11.11"),Some(Version::new(1,11,11)));
+    }
+
+    #[test]
+    fn partial_versions() {
+        assert_eq!(parity_version("2.0"),Some(Version::new(2,0,0)));
+        assert_eq!(parity_version("v2"),Some(Version::new(2,0,0)));
+    }
+
+    #[test]
+    fn channel_names() {
+        assert_eq!(parity_version("stable"),None);
+        assert_eq!(options(None,None).parity_version(),None);
+    }
+
+    #[test]
+    fn base_images() {
+        assert_eq!(options(None,Some("v2.0.8")).base_image(),Some("mimirbridge/pib-parity:2.0.8".to_owned()));
+        assert_eq!(options(Some("example/parity:edge"),Some("2.0.8")).base_image(),Some("example/parity:edge".to_owned()));
+        assert_eq!(options(None,None).base_image(),None);
+    }
+}
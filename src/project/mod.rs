@@ -4,6 +4,14 @@ pub mod node;
 pub mod account;
 pub mod actor;
 pub mod contract;
+pub mod compiler;
+pub mod bundled;
+pub mod deployment;
+pub mod storage;
+pub mod token;
+pub mod network;
+pub mod service;
+pub mod image;
 
 
 pub const CONTRACT_DIR: &'static str = "config/contracts";
@@ -12,10 +20,19 @@ pub const TEMPLATE_DIR: &'static str = "include";
 
 pub const PROJECT_FILE: &'static str = concat!(env!("CARGO_PKG_NAME"),".toml");
 
+pub const CACHE_DIR: &'static str = compiler::cache::CACHE_DIR;
 
-use std::net::Ipv4Addr;
+
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
 use options::{SetupOptions,BuildOptions};
-use project::contract::{ContractConfig,Contract};
+use project::contract::{ContractConfig,Contract,ContractRole,SystemContract};
+use project::bundled::{ValidatorMode,PermissionsConfig};
+use project::deployment::{DeploymentConfigs,Deployment};
+use project::token::TokenConfigs;
+use project::network::{NetworkConfig,ComposeNetworkConfig,PortsConfig,Peering,Addressing};
+use project::service::ServicesConfig;
+use project::image::{DockerConfig,ImageOptions};
+use project::compiler::{CompileContext,SolcConfig,VyperConfig,Cache,Libraries};
 use project::account::Account;
 use project::node::{
     Node,
@@ -31,11 +48,12 @@ use project::actor::{
     Actors,
 };
 use util;
-use types::Error;
-use std::collections::HashMap;
-use std::net::SocketAddrV4;
+use types::{EnodeAddr,Subnet,Error};
+use std::collections::{HashMap,HashSet};
+use std::net::SocketAddr;
 use std::path::Path;
 use std::fs;
+use tempfile;
 use toml;
 
 use types::{ProjectFiles,BuildFiles,SetupFiles};
@@ -70,7 +88,7 @@ impl ProjectContext {
         let project = if self.project.is_some() {
             self.project.as_mut().expect("Project must exist")
         } else {
-            let project = Project::try_from(self.project_config.clone(),options.no_solc)?;
+            let project = Project::try_from(self.project_config.clone(),options)?;
             self.project.get_or_insert(project)
         };
         let project_files = &self.project_files;
@@ -144,22 +162,262 @@ impl ProjectInfo {
 }
 
 
+/// docker-compose settings
+///
+/// ```toml
+/// [docker-compose]
+/// subnet = "172.28.0.0/16"
+/// expose-iface = true
+/// ```
+///
 #[derive(Debug,Clone,Serialize,Deserialize)]
 #[serde(rename_all = "kebab-case",deny_unknown_fields)]
 pub struct DockerComposeConfig {
-    pub gateway_addr: Ipv4Addr,
+    /// Gateway of the compose network (defaults to the first host of `subnet`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub gateway_addr: Option<Ipv4Addr>,
+    /// Subnet of the compose network (defaults to the `/24` containing `gateway-addr`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<Subnet>,
+    /// Gateway of an additional ipv6 subnet (required by nodes w/ ipv6 addresses)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub ipv6_gateway_addr: Option<Ipv6Addr>,
     pub expose_iface: bool,
+    /// Named networks, in addition to the default network
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<ComposeNetworkConfig>,
+    /// Host ports published for each node (supersedes `expose-iface`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub ports: Option<PortsConfig>,
+    /// Restart policy, healthcheck, limits, logging, labels & ordering of the node services
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub services: Option<ServicesConfig>,
 }
 
 
 impl Default for DockerComposeConfig {
 
     fn default() -> Self {
-        Self { gateway_addr: Ipv4Addr::new(10,0,0,1), expose_iface: true }
+        let (subnet,ipv6_gateway_addr,networks,ports,services) = Default::default();
+        Self { gateway_addr: Some(default_gateway()), subnet, ipv6_gateway_addr, expose_iface: true, networks, ports, services }
+    }
+}
+
+
+/// Gateway of the compose network if neither `gateway-addr` nor `subnet` is specified
+fn default_gateway() -> Ipv4Addr { Ipv4Addr::new(10,0,0,1) }
+
+
+impl DockerComposeConfig {
+
+    pub fn gateway(&self) -> IpAddr {
+        match (self.gateway_addr,self.subnet) {
+            (Some(gateway),_) => gateway.into(),
+            (None,Some(subnet)) => subnet.gateway(),
+            (None,None) => default_gateway().into(),
+        }
+    }
+
+    /// Subnet of the compose network, checked to contain the gateway.
+    pub fn subnet(&self) -> Result<Subnet,Error> {
+        let gateway = self.gateway();
+        let subnet = match self.subnet {
+            Some(subnet) => subnet,
+            None => Subnet::new(gateway,24)?,
+        };
+        if !subnet.is_ipv4() {
+            let msg = format!("subnet `{}` must be ipv4 (see `ipv6-gateway-addr`)",subnet);
+            Err(Error::message(msg))
+        } else if subnet.is_host(gateway) {
+            Ok(subnet)
+        } else {
+            let msg = format!("gateway `{}` is not a host address of subnet `{}`",gateway,subnet);
+            Err(Error::message(msg))
+        }
+    }
+
+    /// Subnet of the ipv6 compose network (if any).
+    pub fn ipv6_subnet(&self) -> Result<Option<Subnet>,Error> {
+        match self.ipv6_gateway_addr {
+            Some(gateway) => Ok(Some(Subnet::new(gateway.into(),64)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Allocate `count` host addresses, skipping the gateway and any addresses in `used`.
+    pub fn allocate(&self, count: usize, used: &HashSet<IpAddr>) -> Result<Vec<IpAddr>,Error> {
+        let subnet = self.subnet()?;
+        let gateway = self.gateway();
+        let addrs: Vec<IpAddr> = subnet.hosts()
+            .filter(|addr| *addr != gateway && !used.contains(addr))
+            .take(count)
+            .collect();
+        if addrs.len() < count {
+            let msg = format!("subnet `{}` has too few free addresses for {} nodes",subnet,count);
+            return Err(Error::message(msg));
+        }
+        Ok(addrs)
+    }
+}
+
+
+/// Kubernetes manifest settings
+///
+/// ```toml
+/// [kubernetes]
+/// namespace = "pib"
+/// storage = "10Gi"
+/// storage-class = "standard"
+/// ```
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct KubernetesConfig {
+    /// Namespace of all generated resources
+    #[serde(default = "KubernetesConfig::default_namespace")]
+    pub namespace: String,
+    /// Image run by each node, unless set under `[docker]`
+    #[serde(default = "KubernetesConfig::default_image")]
+    pub image: String,
+    /// Size of the volume claimed for each node's chain state
+    #[serde(default = "KubernetesConfig::default_storage")]
+    pub storage: String,
+    /// Storage class of the state volumes (defaults to the cluster default)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+    /// DNS domain of the cluster
+    #[serde(default = "KubernetesConfig::default_cluster_domain")]
+    pub cluster_domain: String,
+}
+
+
+impl Default for KubernetesConfig {
+
+    fn default() -> Self {
+        Self {
+            namespace: Self::default_namespace(),
+            image: Self::default_image(),
+            storage: Self::default_storage(),
+            storage_class: None,
+            cluster_domain: Self::default_cluster_domain(),
+        }
+    }
+}
+
+
+impl KubernetesConfig {
+
+    fn default_namespace() -> String { "default".into() }
+
+    fn default_image() -> String { "mimirbridge/pib-parity:latest".into() }
+
+    fn default_storage() -> String { "1Gi".into() }
+
+    fn default_cluster_domain() -> String { "cluster.local".into() }
+
+    /// Stable DNS name of the headless service fronting `node`.
+    pub fn service_host(&self, node: &str) -> String {
+        format!("{}.{}.svc.{}",node,self.namespace,self.cluster_domain)
+    }
+}
+
+
+/// Host (non-docker) install settings
+///
+/// ```toml
+/// [host]
+/// nodes = "vm"
+/// user = "parity"
+/// ```
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct HostConfig {
+    /// Install only the nodes with this tag (defaults to all internal nodes)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<String>,
+    /// Directory under which each node's configuration is installed
+    #[serde(default = "HostConfig::default_config_dir")]
+    pub config_dir: String,
+    /// Directory under which each node's keys & chain state are kept
+    #[serde(default = "HostConfig::default_state_dir")]
+    pub state_dir: String,
+    /// User (created if needed) which runs the nodes
+    #[serde(default = "HostConfig::default_user")]
+    pub user: String,
+    /// Path of the parity executable
+    #[serde(default = "HostConfig::default_parity")]
+    pub parity: String,
+}
+
+
+impl Default for HostConfig {
+
+    fn default() -> Self {
+        Self {
+            nodes: None,
+            config_dir: Self::default_config_dir(),
+            state_dir: Self::default_state_dir(),
+            user: Self::default_user(),
+            parity: Self::default_parity(),
+        }
     }
 }
 
 
+impl HostConfig {
+
+    fn default_config_dir() -> String { "/etc/pib".into() }
+
+    fn default_state_dir() -> String { "/var/lib/pib".into() }
+
+    fn default_user() -> String { "pib".into() }
+
+    fn default_parity() -> String { "/usr/bin/parity".into() }
+
+    /// Configuration directory of `node`.
+    pub fn config_dir(&self, node: &str) -> String {
+        format!("{}/{}",self.config_dir.trim_right_matches('/'),node)
+    }
+
+    /// Key & chain state directory of `node`.
+    pub fn state_dir(&self, node: &str) -> String {
+        format!("{}/{}",self.state_dir.trim_right_matches('/'),node)
+    }
+}
+
+
+/// Chain-level settings
+///
+/// ```toml
+/// [chain]
+/// validators = "contract"
+///
+/// [chain.permissions]
+/// transactions = "trusted"
+/// ```
+///
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct ChainConfig {
+    /// How the genesis validator set is specified (`list`, `safe-contract` or `contract`)
+    #[serde(default)]
+    pub validators: ValidatorMode,
+
+    /// Deploy a name registry populated with all accounts
+    #[serde(default)]
+    pub registry: bool,
+
+    /// Deploy a contract recording every node & its reserved peers
+    #[serde(default)]
+    pub topology: bool,
+
+    /// Transaction & node permissioning via the bundled contracts
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+}
+
+
 #[derive(Default,Debug,Clone)]
 pub struct Contracts(Vec<Contract>);
 
@@ -169,6 +427,10 @@ impl Contracts {
     pub fn iter(&self) -> impl Iterator<Item=&Contract> {
         self.0.iter()
     }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Contract> {
+        self.0.iter_mut().find(|contract| contract.name == name)
+    }
 }
 
 
@@ -182,15 +444,40 @@ impl ContractConfigs {
         self.0.extend(other.0);
     }
 
-    pub fn try_load(&self,contract_dir: impl AsRef<Path>, no_solc: bool) -> Result<Contracts,Error> {
+    /// Addresses of all contracts, available for library linking.
+    pub fn libraries(&self) -> Libraries {
+        let mut libraries = Libraries::default();
+        for config in self.0.iter() {
+            libraries.insert(&config.source_file(),config.contract_name(),config.addr);
+        }
+        libraries
+    }
+
+    pub fn try_load(&self, ctx: &CompileContext) -> Result<Contracts,Error> {
         let contracts = self.0.iter()
-            .map(|config| config.load_contract(contract_dir.as_ref(),no_solc))
+            .map(|config| config.load_contract(ctx))
             .collect::<Result<_,_>>()?;
         Ok(Contracts(contracts))
     }
 
     pub fn is_empty(&self) -> bool { self.0.is_empty() }
 
+    /// Name of the contract acting as validator set (if any).
+    pub fn validator_contract(&self) -> Option<&str> {
+        self.0.iter().find(|config| match config.role {
+            Some(ContractRole::Validator(_)) => true,
+            _ => false,
+        }).map(|config| config.name.as_str())
+    }
+
+    /// Name of the contract acting as the specified system contract (if any).
+    pub fn system_contract(&self, role: SystemContract) -> Option<&str> {
+        self.0.iter().find(|config| match config.role {
+            Some(ContractRole::System(other)) => other == role,
+            _ => false,
+        }).map(|config| config.name.as_str())
+    }
+
     pub fn insert(&mut self, config: ContractConfig) {
         self.0.push(config);
     }
@@ -201,28 +488,130 @@ impl ContractConfigs {
 pub struct Project {
     project_info: ProjectInfo,
     docker_compose: Option<DockerComposeConfig>,
+    kubernetes: Option<KubernetesConfig>,
+    host: Option<HostConfig>,
+    docker: Option<DockerConfig>,
     nodes: Nodes,
     actors: Actors,
     contracts: Contracts,
+    deployments: Vec<Deployment>,
+    network: NetworkConfig,
+    peering: Peering,
+    addressing: Addressing,
     templates: HashMap<String,String>,
 }
 
 
 impl Project {
 
-    pub fn try_from(config: ProjectConfig, no_solc: bool) -> Result<Self,Error> {
+    pub fn try_from(config: ProjectConfig, options: &BuildOptions) -> Result<Self,Error> {
         let nodes = Nodes::try_from(config.nodes)?;
         let actors = Actors::try_from(config.actors)?;
-        let contracts = config.contracts.try_load(CONTRACT_DIR,no_solc)?;
+        let network = config.network.unwrap_or_default();
+        let peering = network.peering(&nodes)?;
+        let addressing = match config.docker_compose.as_ref() {
+            Some(compose) => {
+                let default_name = format!("{}-net",config.project_info.project_name);
+                let default_subnets: Vec<Subnet> = Some(compose.subnet()?).into_iter()
+                    .chain(compose.ipv6_subnet()?)
+                    .collect();
+                network::addressing(&compose.networks,&default_name,&default_subnets,&nodes)?
+            },
+            None => Addressing::default(),
+        };
+        let chain = config.chain.unwrap_or_default();
+        if !network.uses_reserved_peers() && network.has_topology() && !chain.topology {
+            warn!("the `[network]` topology is ignored under `bootnodes` discovery (unless recorded by `[chain] topology`)");
+        }
+        let mut contracts = config.contracts;
+        if let Some(bundled) = chain.validators.contract_config() {
+            if let Some(existing) = contracts.validator_contract() {
+                let msg = format!("`[chain] validators` conflicts with validator contract `{}`",existing);
+                return Err(Error::message(msg));
+            }
+            contracts.insert(bundled);
+        }
+        if chain.registry {
+            contracts.insert(bundled::registry_config());
+        }
+        if chain.topology {
+            contracts.insert(bundled::topology_config());
+        }
+        for bundled in chain.permissions.contract_configs() {
+            if let Some(ContractRole::System(role)) = bundled.role {
+                if let Some(existing) = contracts.system_contract(role) {
+                    let msg = format!("`[chain.permissions]` conflicts with `{}` contract `{}`",
+                        role.role_name(),existing);
+                    return Err(Error::message(msg));
+                }
+            }
+            contracts.insert(bundled);
+        }
+        for token in config.tokens.iter() {
+            contracts.insert(token.contract_config());
+        }
+        let deployment_plan = config.deployments.plan(&actors)?;
+        let mut libraries = contracts.libraries();
+        config.deployments.libraries(&deployment_plan,&mut libraries);
+        let scratch = tempfile::Builder::new().prefix("pib-").tempdir()?;
+        compiler::stage_sources(CONTRACT_DIR.as_ref(),scratch.path())?;
+        bundled::stage_sources(scratch.path())?;
+        let compile_ctx = CompileContext {
+            contract_dir: CONTRACT_DIR.into(),
+            scratch_dir: scratch.path().to_owned(),
+            solc: config.solc.unwrap_or_default(),
+            vyper: config.vyper.unwrap_or_default(),
+            libraries: libraries,
+            cache: Cache::new(CACHE_DIR).lookups(!options.no_cache),
+            no_solc: options.no_solc,
+        };
+        let contracts = contracts.try_load(&compile_ctx)?;
+        let deployments = config.deployments.try_load(&deployment_plan,&compile_ctx)?;
         let templates = load_templates(TEMPLATE_DIR)?;
-        Ok(Self {
+        let mut project = Self {
             project_info: config.project_info,
             docker_compose: config.docker_compose,
+            kubernetes: config.kubernetes,
+            host: config.host,
+            docker: config.docker,
             nodes: nodes,
             actors: actors,
             contracts: contracts,
+            deployments: deployments,
+            network: network,
+            peering: peering,
+            addressing: addressing,
             templates: templates
-        })
+        };
+        for token in config.tokens.iter() {
+            let storage = token.storage(&project)?;
+            if let Some(contract) = project.contracts.get_mut(&token.name) {
+                contract.storage = Some(storage);
+            }
+        }
+        if chain.registry {
+            let storage = bundled::registry_storage(&project);
+            if let Some(registry) = project.contracts.get_mut(bundled::REGISTRY_NAME) {
+                registry.storage = Some(storage);
+            }
+        }
+        if chain.topology {
+            let storage = bundled::topology_storage(&project);
+            if let Some(topology) = project.contracts.get_mut(bundled::TOPOLOGY_NAME) {
+                topology.storage = Some(storage);
+            }
+        }
+        if let Some(storage) = chain.permissions.tx_storage(&project) {
+            if let Some(contract) = project.contracts.get_mut(bundled::TX_PERMISSION_NAME) {
+                contract.storage = Some(storage);
+            }
+        }
+        if let Some(storage) = chain.permissions.node_storage(&project) {
+            if let Some(contract) = project.contracts.get_mut(bundled::NODE_PERMISSION_NAME) {
+                contract.storage = Some(storage);
+            }
+        }
+        Ok(project)
     }
 
     pub fn project_name(&self) -> &str {
@@ -233,6 +622,23 @@ impl Project {
         self.docker_compose.as_ref()
     }
 
+    pub fn kubernetes_config(&self) -> Option<&KubernetesConfig> {
+        self.kubernetes.as_ref()
+    }
+
+    pub fn host_config(&self) -> Option<&HostConfig> {
+        self.host.as_ref()
+    }
+
+    pub fn docker_config(&self) -> Option<&DockerConfig> {
+        self.docker.as_ref()
+    }
+
+    /// Base image & parity version of `node`.
+    pub fn image_options(&self, node: &Node) -> ImageOptions {
+        self.docker.as_ref().map(|docker| docker.options(node)).unwrap_or_default()
+    }
+
     pub fn get_template(&self, name: &str) -> Option<&str> {
         self.templates.get(name).map(AsRef::as_ref)
     }
@@ -246,6 +652,40 @@ impl Project {
         self.nodes.iter()
     }
 
+    /// Reserved peers of the named node, according to the network topology.
+    pub fn iter_peers<'a>(&'a self, node: &'a str) -> impl Iterator<Item=Node<'a>> + 'a {
+        self.iter_nodes().filter(move |peer| self.peering.is_peer(node,peer.name()))
+    }
+
+    /// Enode address(es) under which `peer` is reachable from `node`.
+    ///
+    /// Internal nodes reach one another over a shared docker network (if any), while
+    /// external nodes are handled by `Node::reachable_enodes`.
+    pub fn reachable_enodes(&self, peer: &Node, node: &Node) -> Vec<EnodeAddr> {
+        match (peer.internal(),node.internal()) {
+            (Some(internal),Some(_)) => {
+                let default = internal.network_addr().ip();
+                match self.addressing.peer_addr(node.name(),peer.name(),default) {
+                    Some(addr) => vec![internal.enode_addr_at(addr)],
+                    None => {
+                        debug!("`{}` shares no network with peer `{}`",node.name(),peer.name());
+                        Vec::new()
+                    },
+                }
+            },
+            _ => peer.reachable_enodes(node),
+        }
+    }
+
+    /// Named docker networks of a node, w/ its address on each (`None` if on the default network).
+    pub fn compose_networks(&self, node: &str) -> Option<&[(String,IpAddr)]> {
+        self.addressing.networks(node)
+    }
+
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.network
+    }
+
     pub fn iter_actors(&self) -> impl Iterator<Item=Actor> {
         self.actors.iter()
     }
@@ -254,6 +694,19 @@ impl Project {
         self.contracts.iter()
     }
 
+    pub fn iter_deployments(&self) -> impl Iterator<Item=&Deployment> {
+        self.deployments.iter()
+    }
+
+    /// Node responsible for submitting deployment transactions (if any).
+    pub fn deployment_node(&self) -> Option<Node> {
+        if !self.deployments.is_empty() {
+            self.iter_nodes().find(|n| n.is_interface() && n.internal().is_some())
+        } else {
+            None
+        }
+    }
+
     pub fn iter_accounts(&self) -> impl Iterator<Item=Account> {
         self.iter_nodes().map(From::from).chain(
             self.iter_actors().map(From::from).chain(
@@ -290,27 +743,47 @@ pub struct ProjectConfig {
     project_info: ProjectInfo,
     #[serde(default,skip_serializing_if = "Option::is_none")]
     docker_compose: Option<DockerComposeConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    kubernetes: Option<KubernetesConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    host: Option<HostConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    docker: Option<DockerConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    chain: Option<ChainConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    network: Option<NetworkConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    solc: Option<SolcConfig>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    vyper: Option<VyperConfig>,
     #[serde(rename = "node",default)]
     nodes: NodeConfigs,
     #[serde(rename = "actor",default,skip_serializing_if = "ActorConfigs::is_empty")]
     actors: ActorConfigs,
     #[serde(rename = "contract",default,skip_serializing_if = "ContractConfigs::is_empty")]
-    contracts: ContractConfigs
+    contracts: ContractConfigs,
+    #[serde(rename = "deployment",default,skip_serializing_if = "DeploymentConfigs::is_empty")]
+    deployments: DeploymentConfigs,
+    #[serde(rename = "token",default,skip_serializing_if = "TokenConfigs::is_empty")]
+    tokens: TokenConfigs,
 }
 
 
 impl ProjectConfig {
 
-    pub fn new(name: String, authority_count: u16, interface_count: u16, actor_count: u16) -> Self {
+    pub fn new(name: String, authority_count: u16, interface_count: u16, actor_count: u16,
+               subnet: Option<Subnet>) -> Result<Self,Error> {
         let project_info = ProjectInfo::new(name);
-        let compose = DockerComposeConfig::default();
-        let gateway: u32 = compose.gateway_addr.into();
-        let start_addr = gateway + 1;
+        let mut compose = DockerComposeConfig::default();
+        if subnet.is_some() {
+            compose.gateway_addr = None;
+            compose.subnet = subnet;
+        }
         let mut nodes = NodeConfigs::default();
-        let addrs = (0u32..).into_iter().map(|offset| {
-            let ip_addr = Ipv4Addr::from(start_addr + offset);
-            SocketAddrV4::new(ip_addr,30303)
-        });
+        let node_count = authority_count as usize + interface_count as usize;
+        let addrs = compose.allocate(node_count,&HashSet::new())?.into_iter()
+            .map(|ip_addr| SocketAddr::new(ip_addr,30303));
         let roles = (0..authority_count).into_iter().map(|_|NodeRole::Authority)
             .chain((0..interface_count).into_iter().map(|_|NodeRole::Interface));
         for (index,(addr,role)) in addrs.zip(roles).enumerate() {
@@ -325,8 +798,8 @@ impl ProjectConfig {
             actors.insert(actor);
         }
         let docker_compose = Some(compose);
-        let contracts = Default::default();
-        Self { project_info, docker_compose, nodes, actors, contracts }
+        let (kubernetes,host,docker,chain,network,solc,vyper,contracts,deployments,tokens) = Default::default();
+        Ok(Self { project_info, docker_compose, kubernetes, host, docker, chain, network, solc, vyper, nodes, actors, contracts, deployments, tokens })
     }
 
     pub fn project_name(&self) -> &str { &self.project_info.project_name }
@@ -334,11 +807,20 @@ impl ProjectConfig {
     pub fn nodes_mut(&mut self) -> &mut NodeConfigs { &mut self.nodes }
 
     pub fn import(&mut self, other: Self) {
-        let Self { nodes, actors, contracts, docker_compose, .. } = other;
+        let Self { nodes, actors, contracts, deployments, tokens, docker_compose, kubernetes, host, docker, chain, network, solc, vyper, .. } = other;
         if docker_compose.is_some() { self.docker_compose = docker_compose; }
+        if kubernetes.is_some() { self.kubernetes = kubernetes; }
+        if host.is_some() { self.host = host; }
+        if docker.is_some() { self.docker = docker; }
+        if chain.is_some() { self.chain = chain; }
+        if network.is_some() { self.network = network; }
+        if solc.is_some() { self.solc = solc; }
+        if vyper.is_some() { self.vyper = vyper; }
         self.nodes.import(nodes);
         self.actors.import(actors);
         self.contracts.import(contracts);
+        self.deployments.import(deployments);
+        self.tokens.import(tokens);
     }
 
     pub fn insert_contract(&mut self, contract: ContractConfig) {
@@ -359,3 +841,65 @@ impl ProjectConfig {
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap,HashSet};
+    use std::net::IpAddr;
+    use toml;
+    use super::{DockerComposeConfig,Project,ProjectInfo,Contracts,Nodes,Actors,NetworkConfig,Addressing};
+
+    /// Project of the given nodes, actors & network settings (without contracts or templates).
+    pub fn project(nodes: &str, actors: &str, network: &str) -> Project {
+        let nodes = Nodes::try_from(toml::from_str(nodes).unwrap()).unwrap();
+        let actors = Actors::try_from(toml::from_str(actors).unwrap()).unwrap();
+        let network: NetworkConfig = toml::from_str(network).unwrap();
+        let peering = network.peering(&nodes).unwrap();
+        Project {
+            project_info: ProjectInfo { project_name: "pib".to_owned() },
+            docker_compose: None,
+            kubernetes: None,
+            host: None,
+            docker: None,
+            nodes: nodes,
+            actors: actors,
+            contracts: Contracts::default(),
+            deployments: Vec::new(),
+            network: network,
+            peering: peering,
+            addressing: Addressing::default(),
+            templates: HashMap::new(),
+        }
+    }
+
+    fn addrs(addrs: &[&str]) -> Vec<IpAddr> {
+        addrs.iter().map(|addr| addr.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn allocate_skips_gateway() {
+        let compose = DockerComposeConfig::default();
+        let allocated = compose.allocate(3,&HashSet::new()).unwrap();
+        assert_eq!(allocated,addrs(&["10.0.0.2","10.0.0.3","10.0.0.4"]));
+    }
+
+    #[test]
+    fn allocate_skips_used() {
+        let compose = DockerComposeConfig::default();
+        let used = addrs(&["10.0.0.2","10.0.0.4"]).into_iter().collect();
+        let allocated = compose.allocate(2,&used).unwrap();
+        assert_eq!(allocated,addrs(&["10.0.0.3","10.0.0.5"]));
+    }
+
+    #[test]
+    fn allocate_within_subnet() {
+        let compose = DockerComposeConfig {
+            gateway_addr: None,
+            subnet: Some("172.28.0.0/30".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(compose.allocate(1,&HashSet::new()).unwrap(),addrs(&["172.28.0.2"]));
+        assert!(compose.allocate(2,&HashSet::new()).is_err());
+    }
+}
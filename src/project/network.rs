@@ -0,0 +1,640 @@
+//! Network-level settings (e.g. peer topology & docker networks).
+use project::node::{Node,Nodes};
+use types::{Subnet,Error};
+use std::collections::{BTreeMap,HashMap,HashSet};
+use std::net::{IpAddr,Ipv4Addr};
+use std::fmt;
+
+
+/// Network-level settings
+///
+/// ```toml
+/// [network]
+/// topology = "star"
+/// hubs = "hub"
+/// discovery = "reserved-only"
+/// ```
+///
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Shape of the reserved-peer graph
+    #[serde(default)]
+    pub topology: Topology,
+
+    /// Tag of the hub nodes (required by the `star` topology)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub hubs: Option<String>,
+
+    /// Peers of each node, by name (required by the `explicit` topology)
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub peers: BTreeMap<String,Vec<String>>,
+
+    /// How nodes find their peers
+    #[serde(default)]
+    pub discovery: Discovery,
+
+    /// Tag of the bootnodes (required by the `bootnodes` discovery mode)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub bootnodes: Option<String>,
+}
+
+
+/// Shape of the reserved-peer graph
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Topology {
+    /// Every node peers with every other node
+    FullMesh,
+    /// Each node peers with its neighbours, in order of declaration
+    Ring,
+    /// Hub nodes peer with every node; all other nodes peer with the hubs only
+    Star,
+    /// Authorities form a full mesh; each interface peers with a single authority
+    AuthorityMesh,
+    /// Peers are listed explicitly under `[network.peers]`
+    Explicit,
+}
+
+
+impl Default for Topology {
+
+    fn default() -> Self { Topology::FullMesh }
+}
+
+
+/// How nodes find their peers
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Discovery {
+    /// Reserved peers (per the topology), w/ parity's discovery left enabled
+    Reserved,
+    /// Reserved peers only; all other connections are refused
+    ReservedOnly,
+    /// Discovery via the tagged bootnodes, w/o reserved peers
+    Bootnodes,
+}
+
+
+impl Default for Discovery {
+
+    fn default() -> Self { Discovery::Reserved }
+}
+
+
+impl NetworkConfig {
+
+    /// Whether nodes use reserved peers (i.e. `peers.txt`).
+    pub fn uses_reserved_peers(&self) -> bool {
+        self.discovery != Discovery::Bootnodes
+    }
+
+    /// Whether a topology other than the default full mesh is configured.
+    pub fn has_topology(&self) -> bool {
+        self.topology != Topology::default() || self.hubs.is_some() || !self.peers.is_empty()
+    }
+
+    /// Bootnodes, if in the `bootnodes` discovery mode.
+    pub fn bootnodes<'a>(&self, nodes: impl Iterator<Item=Node<'a>>) -> Result<Option<Vec<Node<'a>>>,Error> {
+        if self.discovery != Discovery::Bootnodes {
+            return Ok(None);
+        }
+        let tag = self.bootnodes.as_ref().ok_or_else(|| {
+            Error::message("`bootnodes` discovery requires `[network] bootnodes`")
+        })?;
+        let bootnodes: Vec<Node> = nodes.filter(|node| node.tags().contains(tag)).collect();
+        if bootnodes.is_empty() {
+            let msg = format!("`bootnodes` discovery requires at least one node tagged `{}`",tag);
+            return Err(Error::message(msg));
+        }
+        Ok(Some(bootnodes))
+    }
+
+    /// Compute the peers of every node.  Peering is always symmetric, and no node is its own peer.
+    pub fn peering(&self, nodes: &Nodes) -> Result<Peering,Error> {
+        let nodes: Vec<Node> = nodes.iter().collect();
+        let mut peering = Peering::default();
+        match self.topology {
+            Topology::FullMesh => {
+                for (index,node) in nodes.iter().enumerate() {
+                    for peer in nodes[index + 1..].iter() {
+                        peering.connect(node.name(),peer.name());
+                    }
+                }
+            },
+            Topology::Ring => {
+                if nodes.len() > 1 {
+                    for (index,node) in nodes.iter().enumerate() {
+                        let next = &nodes[(index + 1) % nodes.len()];
+                        peering.connect(node.name(),next.name());
+                    }
+                }
+            },
+            Topology::Star => {
+                let tag = self.hubs.as_ref().ok_or_else(|| {
+                    Error::message("`star` topology requires `[network] hubs`")
+                })?;
+                let (hubs,spokes): (Vec<&Node>,Vec<&Node>) = nodes.iter()
+                    .partition(|node| node.tags().contains(tag));
+                if hubs.is_empty() {
+                    let msg = format!("`star` topology requires at least one node tagged `{}`",tag);
+                    return Err(Error::message(msg));
+                }
+                for (index,hub) in hubs.iter().enumerate() {
+                    for peer in hubs[index + 1..].iter().chain(spokes.iter()) {
+                        peering.connect(hub.name(),peer.name());
+                    }
+                }
+            },
+            Topology::AuthorityMesh => {
+                let (authorities,interfaces): (Vec<&Node>,Vec<&Node>) = nodes.iter()
+                    .partition(|node| node.is_authority());
+                if authorities.is_empty() {
+                    return Err(Error::message("`authority-mesh` topology requires at least one authority"));
+                }
+                for (index,authority) in authorities.iter().enumerate() {
+                    for peer in authorities[index + 1..].iter() {
+                        peering.connect(authority.name(),peer.name());
+                    }
+                }
+                for (index,interface) in interfaces.iter().enumerate() {
+                    let authority = authorities[index % authorities.len()];
+                    peering.connect(interface.name(),authority.name());
+                }
+            },
+            Topology::Explicit => {
+                let names: HashSet<&str> = nodes.iter().map(|node| node.name()).collect();
+                for (name,peers) in self.peers.iter() {
+                    for peer in Some(name).into_iter().chain(peers.iter()) {
+                        if !names.contains(peer.as_str()) {
+                            let msg = format!("unknown node `{}` in `[network.peers]`",peer);
+                            return Err(Error::message(msg));
+                        }
+                    }
+                    for peer in peers.iter().filter(|peer| *peer != name) {
+                        peering.connect(name,peer);
+                    }
+                }
+            },
+        }
+        if self.topology != Topology::Explicit && !self.peers.is_empty() {
+            warn!("`[network.peers]` is ignored unless `topology = \"explicit\"`");
+        }
+        Ok(peering)
+    }
+}
+
+
+/// Peers of each node, by name
+#[derive(Default,Debug,Clone)]
+pub struct Peering {
+    inner: HashMap<String,HashSet<String>>,
+}
+
+
+impl Peering {
+
+    fn connect(&mut self, a: &str, b: &str) {
+        self.inner.entry(a.to_owned()).or_default().insert(b.to_owned());
+        self.inner.entry(b.to_owned()).or_default().insert(a.to_owned());
+    }
+
+    pub fn is_peer(&self, node: &str, peer: &str) -> bool {
+        self.inner.get(node).map(|peers| peers.contains(peer)).unwrap_or(false)
+    }
+}
+
+
+/// Named docker network, used to partition internal nodes
+///
+/// ```toml
+/// [[docker-compose.networks]]
+/// name = "east"
+/// subnet = "172.30.0.0/24"
+/// nodes = ["node-0","node-1"]
+/// tag = "east"
+/// addrs = { node-0 = "172.30.0.10" }
+/// ```
+///
+/// Members are the listed nodes plus any nodes with the given tag.  Nodes which belong
+/// to one or more named networks are removed from the default network, and nodes which
+/// belong to several act as bridges between them.  Addresses not given in `addrs` are
+/// allocated from the subnet in order of declaration.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct ComposeNetworkConfig {
+    pub name: String,
+
+    pub subnet: Subnet,
+
+    /// Gateway of the network (defaults to the first host of `subnet`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub gateway_addr: Option<IpAddr>,
+
+    /// Names of member nodes
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<String>,
+
+    /// Tag of member nodes
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    /// Explicit addresses of member nodes, by name
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub addrs: BTreeMap<String,IpAddr>,
+}
+
+
+impl ComposeNetworkConfig {
+
+    pub fn gateway(&self) -> IpAddr {
+        self.gateway_addr.unwrap_or_else(|| self.subnet.gateway())
+    }
+
+    fn is_member(&self, node: &Node) -> bool {
+        self.nodes.iter().any(|name| name == node.name())
+            || self.tag.as_ref().map(|tag| node.tags().contains(tag)).unwrap_or(false)
+    }
+
+    /// Assign an address to every member of the network.
+    fn assign(&self, nodes: &[Node], addressing: &mut Addressing) -> Result<(),Error> {
+        let gateway = self.gateway();
+        if !self.subnet.is_host(gateway) {
+            let msg = format!("gateway `{}` of network `{}` is not a host address of `{}`",
+                gateway,self.name,self.subnet);
+            return Err(Error::message(msg));
+        }
+        for name in self.nodes.iter().chain(self.addrs.keys()) {
+            match nodes.iter().find(|node| node.name() == name) {
+                Some(node) if node.internal().is_none() => {
+                    let msg = format!("external node `{}` cannot join network `{}`",name,self.name);
+                    return Err(Error::message(msg));
+                },
+                Some(node) if !self.is_member(node) => {
+                    let msg = format!("node `{}` has an address on network `{}`, but is not a member",
+                        name,self.name);
+                    return Err(Error::message(msg));
+                },
+                Some(_) => { },
+                None => {
+                    let msg = format!("unknown node `{}` in network `{}`",name,self.name);
+                    return Err(Error::message(msg));
+                },
+            }
+        }
+        let mut used = HashSet::new();
+        used.insert(gateway);
+        for (name,addr) in self.addrs.iter() {
+            if !self.subnet.is_host(*addr) || !used.insert(*addr) {
+                let msg = format!("address `{}` of node `{}` is not a free host address of `{}`",
+                    addr,name,self.subnet);
+                return Err(Error::message(msg));
+            }
+        }
+        let mut free = self.subnet.hosts().filter(|addr| !used.contains(addr));
+        for node in nodes.iter().filter(|node| node.internal().is_some() && self.is_member(node)) {
+            let addr = match self.addrs.get(node.name()) {
+                Some(addr) => *addr,
+                None => free.next().ok_or_else(|| {
+                    Error::message(format!("network `{}` has too few free addresses",self.name))
+                })?,
+            };
+            addressing.inner.entry(node.name().to_owned()).or_default()
+                .push((self.name.clone(),addr));
+        }
+        Ok(())
+    }
+}
+
+
+/// Assign addresses to the members of all named networks, checking that no two networks
+/// overlap.  The default network (`default_name`, w/ subnets `default_subnets`) is only
+/// checked if some internal node remains on it.
+pub fn addressing(networks: &[ComposeNetworkConfig], default_name: &str, default_subnets: &[Subnet],
+                  nodes: &Nodes) -> Result<Addressing,Error> {
+    let nodes: Vec<Node> = nodes.iter().collect();
+    let mut addressing = Addressing::default();
+    let mut names = HashSet::new();
+    for network in networks {
+        if network.name == default_name {
+            let msg = format!("network `{}` clashes with the default network of the project",network.name);
+            return Err(Error::message(msg));
+        }
+        if !names.insert(network.name.as_str()) {
+            return Err(Error::message(format!("duplicate network `{}`",network.name)));
+        }
+        network.assign(&nodes,&mut addressing)?;
+    }
+    let mut subnets: Vec<(&str,Subnet)> = Vec::new();
+    let uses_default = nodes.iter().filter_map(|node| node.internal())
+        .any(|node| addressing.networks(node.name()).is_none());
+    if uses_default {
+        subnets.extend(default_subnets.iter().map(|subnet| (default_name,*subnet)));
+    }
+    subnets.extend(networks.iter().map(|network| (network.name.as_str(),network.subnet)));
+    for (index,(name,subnet)) in subnets.iter().enumerate() {
+        if let Some((other,other_subnet)) = subnets[..index].iter().find(|(_,other)| other.overlaps(subnet)) {
+            let msg = format!("subnet `{}` of network `{}` overlaps subnet `{}` of network `{}`",
+                subnet,name,other_subnet,other);
+            return Err(Error::message(msg));
+        }
+    }
+    Ok(addressing)
+}
+
+
+/// Addresses of internal nodes on named networks
+#[derive(Default,Debug,Clone)]
+pub struct Addressing {
+    inner: HashMap<String,Vec<(String,IpAddr)>>,
+}
+
+
+impl Addressing {
+
+    /// Named networks of a node, w/ its address on each (`None` if on the default network).
+    pub fn networks(&self, node: &str) -> Option<&[(String,IpAddr)]> {
+        self.inner.get(node).map(|networks| networks.as_slice())
+    }
+
+    /// Address of `peer` as reachable from `node`, given the peer's address on the default
+    /// network.  Returns `None` if the two nodes share no network.
+    pub fn peer_addr(&self, node: &str, peer: &str, default: IpAddr) -> Option<IpAddr> {
+        match (self.networks(node),self.networks(peer)) {
+            (None,None) => Some(default),
+            (Some(networks),Some(peer_networks)) => {
+                peer_networks.iter()
+                    .find(|(name,_)| networks.iter().any(|(other,_)| other == name))
+                    .map(|(_,addr)| *addr)
+            },
+            _ => None,
+        }
+    }
+}
+
+
+/// Host ports published for each node
+///
+/// ```toml
+/// [docker-compose.ports]
+/// base = 8545
+/// step = 10
+/// bind = "127.0.0.1"
+/// services = ["rpc","ws","p2p"]
+/// authorities = true
+/// overrides = { node-3 = 9000 }
+/// ```
+///
+/// The n-th published node is assigned the host ports `base + n * step` onward (or its
+/// override onward), in the order `rpc`, `ws`, `p2p`.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct PortsConfig {
+    /// Host port of the first published node
+    #[serde(default = "default_base_port")]
+    pub base: u16,
+
+    /// Increment of the host ports per published node
+    #[serde(default = "default_port_step")]
+    pub step: u16,
+
+    /// Host address to which ports are bound
+    #[serde(default = "default_bind_addr")]
+    pub bind: Ipv4Addr,
+
+    /// Services to publish
+    #[serde(default = "default_services")]
+    pub services: Vec<PortService>,
+
+    /// Publish ports of authorities as well as interfaces
+    #[serde(default)]
+    pub authorities: bool,
+
+    /// First host port of individual nodes, by name
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String,u16>,
+}
+
+
+impl Default for PortsConfig {
+
+    fn default() -> Self {
+        Self {
+            base: default_base_port(),
+            step: default_port_step(),
+            bind: default_bind_addr(),
+            services: default_services(),
+            authorities: false,
+            overrides: Default::default(),
+        }
+    }
+}
+
+
+fn default_base_port() -> u16 { 8545 }
+
+fn default_port_step() -> u16 { 10 }
+
+fn default_bind_addr() -> Ipv4Addr { Ipv4Addr::new(127,0,0,1) }
+
+fn default_services() -> Vec<PortService> { vec![PortService::Rpc,PortService::Ws] }
+
+
+impl PortsConfig {
+
+    /// Host port of `service` for the node published at position `index`.
+    pub fn host_port(&self, node: &str, index: usize, service: PortService) -> Result<u16,Error> {
+        let first = match self.overrides.get(node) {
+            Some(port) => Some(*port),
+            None => (index as u64).checked_mul(self.step as u64)
+                .and_then(|offset| offset.checked_add(self.base as u64))
+                .filter(|port| *port <= u16::max_value() as u64)
+                .map(|port| port as u16),
+        };
+        first.and_then(|port| port.checked_add(service.offset())).ok_or_else(|| {
+            Error::message(format!("host port of `{}` for node `{}` exceeds 65535",service,node))
+        })
+    }
+}
+
+
+/// A service published on the host
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortService {
+    /// JSON-RPC over http (8545)
+    Rpc,
+    /// JSON-RPC over websockets (8546)
+    Ws,
+    /// devp2p (the node's network port, tcp & udp)
+    P2p,
+}
+
+
+impl PortService {
+
+    /// Offset of the service's host port from the node's first host port.
+    pub fn offset(&self) -> u16 {
+        match self {
+            PortService::Rpc => 0,
+            PortService::Ws => 1,
+            PortService::P2p => 2,
+        }
+    }
+}
+
+
+impl fmt::Display for PortService {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PortService::Rpc => f.write_str("rpc"),
+            PortService::Ws => f.write_str("ws"),
+            PortService::P2p => f.write_str("p2p"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use project::node::{Nodes,NodeConfigs};
+    use types::Subnet;
+    use std::net::IpAddr;
+    use toml;
+    use super::{ComposeNetworkConfig,Addressing,addressing};
+
+    const NODES: &str = r#"
+        [[internal]]
+        node-name = "node-0"
+        network-addr = "10.0.0.2:30303"
+
+        [[internal]]
+        node-name = "node-1"
+        network-addr = "10.0.0.3:30303"
+        tags = ["bridge"]
+
+        [[internal]]
+        node-name = "node-2"
+        network-addr = "10.0.0.4:30303"
+
+        [[internal]]
+        node-name = "node-3"
+        network-addr = "10.0.0.5:30303"
+    "#;
+
+    #[derive(Deserialize)]
+    struct Networks {
+        networks: Vec<ComposeNetworkConfig>,
+    }
+
+    fn nodes() -> Nodes {
+        let configs: NodeConfigs = toml::from_str(NODES).unwrap();
+        Nodes::try_from(configs).unwrap()
+    }
+
+    fn networks(raw: &str) -> Vec<ComposeNetworkConfig> {
+        toml::from_str::<Networks>(raw).unwrap().networks
+    }
+
+    fn default_subnets() -> Vec<Subnet> {
+        vec!["10.0.0.0/24".parse().unwrap()]
+    }
+
+    fn assign(raw: &str) -> Addressing {
+        addressing(&networks(raw),"pib-net",&default_subnets(),&nodes()).unwrap()
+    }
+
+    fn addr(addr: &str) -> IpAddr { addr.parse().unwrap() }
+
+    /// `node-1` bridges `east` (w/ `node-0`) & `west` (w/ `node-2`); `node-3` stays on the
+    /// default network.
+    const PARTITIONED: &str = r#"
+        [[networks]]
+        name = "east"
+        subnet = "172.30.0.0/24"
+        nodes = ["node-0"]
+        tag = "bridge"
+
+        [[networks]]
+        name = "west"
+        subnet = "172.31.0.0/24"
+        nodes = ["node-1","node-2"]
+        addrs = { node-2 = "172.31.0.100" }
+    "#;
+
+    #[test]
+    fn assigns_members() {
+        let addressing = assign(PARTITIONED);
+        let east = vec![("east".to_owned(),addr("172.30.0.2"))];
+        assert_eq!(addressing.networks("node-0"),Some(east.as_slice()));
+        let bridge = vec![("east".to_owned(),addr("172.30.0.3")),("west".to_owned(),addr("172.31.0.2"))];
+        assert_eq!(addressing.networks("node-1"),Some(bridge.as_slice()));
+        let west = vec![("west".to_owned(),addr("172.31.0.100"))];
+        assert_eq!(addressing.networks("node-2"),Some(west.as_slice()));
+        assert_eq!(addressing.networks("node-3"),None);
+    }
+
+    #[test]
+    fn resolves_peers_across_bridges() {
+        let addressing = assign(PARTITIONED);
+        let default = addr("10.0.0.3");
+        assert_eq!(addressing.peer_addr("node-0","node-1",default),Some(addr("172.30.0.3")));
+        assert_eq!(addressing.peer_addr("node-2","node-1",default),Some(addr("172.31.0.2")));
+        assert_eq!(addressing.peer_addr("node-1","node-2",default),Some(addr("172.31.0.100")));
+    }
+
+    #[test]
+    fn nodes_without_shared_networks() {
+        let addressing = assign(PARTITIONED);
+        assert_eq!(addressing.peer_addr("node-0","node-2",addr("10.0.0.4")),None);
+        assert_eq!(addressing.peer_addr("node-3","node-0",addr("10.0.0.2")),None);
+        assert_eq!(addressing.peer_addr("node-0","node-3",addr("10.0.0.5")),None);
+        let unpartitioned = Addressing::default();
+        assert_eq!(unpartitioned.peer_addr("node-0","node-3",addr("10.0.0.5")),Some(addr("10.0.0.5")));
+    }
+
+    #[test]
+    fn rejects_overlapping_subnets() {
+        let overlapping = networks(r#"
+            [[networks]]
+            name = "east"
+            subnet = "172.30.0.0/24"
+            nodes = ["node-0"]
+
+            [[networks]]
+            name = "west"
+            subnet = "172.30.0.128/25"
+            nodes = ["node-1"]
+        "#);
+        assert!(addressing(&overlapping,"pib-net",&default_subnets(),&nodes()).is_err());
+    }
+
+    #[test]
+    fn checks_default_network_only_while_in_use() {
+        let raw = r#"
+            [[networks]]
+            name = "wide"
+            subnet = "10.0.0.0/16"
+            tag = "all"
+            nodes = ["node-0","node-1","node-2"]
+        "#;
+        // `node-3` remains on the (overlapping) default network
+        assert!(addressing(&networks(raw),"pib-net",&default_subnets(),&nodes()).is_err());
+        let all = raw.replace(r#"nodes = ["node-0","node-1","node-2"]"#,r#"nodes = ["node-0","node-1","node-2","node-3"]"#);
+        assert!(addressing(&networks(&all),"pib-net",&default_subnets(),&nodes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_default_network_name() {
+        let clashing = networks(r#"
+            [[networks]]
+            name = "pib-net"
+            subnet = "172.30.0.0/24"
+            nodes = ["node-0"]
+        "#);
+        assert!(addressing(&clashing,"pib-net",&default_subnets(),&nodes()).is_err());
+    }
+}
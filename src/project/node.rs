@@ -1,6 +1,6 @@
 use mimir_crypto::secp256k1::{Address,Secret,Signer};
 use types::{Tags,Include,EnodeAddr,Error};
-use std::net::SocketAddrV4;
+use std::net::{IpAddr,SocketAddr};
 use std::path::Path;
 use std::fs;
 use util;
@@ -37,17 +37,29 @@ impl<'a> Node<'a> {
         }
     }
 
-    pub fn network_addr(&self) -> SocketAddrV4 {
+    /// Socket address of the node (unavailable for external nodes identified by hostname).
+    pub fn network_addr(&self) -> Option<SocketAddr> {
         match self {
-            Node::Internal(node) => node.enode_addr().addr,
-            Node::External(node) => node.enode_addr.addr,
+            Node::Internal(node) => Some(node.network_addr()),
+            Node::External(node) => node.enode_addr.socket_addr(),
         }
     }
 
     pub fn enode_addr(&self) -> EnodeAddr {
         match self {
             Node::Internal(node) => node.enode_addr(),
-            Node::External(node) => node.enode_addr,
+            Node::External(node) => node.enode_addr.clone(),
+        }
+    }
+
+    /// Enode address(es) under which this node is reachable from `peer`.
+    ///
+    /// Internal nodes reach one another via their primary address, while external
+    /// nodes are given the public address (if any) followed by any alternates.
+    pub fn reachable_enodes(&self, peer: &Node) -> Vec<EnodeAddr> {
+        match (self, peer) {
+            (Node::Internal(node), Node::External(_)) => node.public_enodes(),
+            _ => vec![self.enode_addr()],
         }
     }
 
@@ -121,7 +133,9 @@ pub type ExternalNode = ExternalNodeConfig;
 pub struct InternalNode {
     node_name: String,
     node_role: NodeRole,
-    network_addr: SocketAddrV4, 
+    network_addr: SocketAddr,
+    public_addr: Option<SocketAddr>,
+    alt_addrs: Vec<SocketAddr>,
     account_pass: String,
     account_signer: Signer,
     network_signer: Signer,
@@ -145,6 +159,8 @@ impl InternalNode {
             node_name: config.node_name,
             node_role: config.node_role,
             network_addr: config.network_addr,
+            public_addr: config.public_addr,
+            alt_addrs: config.alt_addrs.unwrap_or_default(),
             account_pass: account_pass,
             account_signer: account_signer,
             network_signer: network_signer,
@@ -191,14 +207,37 @@ impl InternalNode {
     }
 
     pub fn enode_addr(&self) -> EnodeAddr {
-        let address = self.network_addr.into();
         let public = self.network_signer.public();
-        EnodeAddr::new(public,address)
+        EnodeAddr::new(public,self.network_addr)
     }
 
-    pub fn network_addr(&self) -> SocketAddrV4 {
+    pub fn network_addr(&self) -> SocketAddr {
         self.network_addr
     }
+
+    /// Enode address of the node at an alternative ip (e.g. on another docker network).
+    pub fn enode_addr_at(&self, ip: IpAddr) -> EnodeAddr {
+        let public = self.network_signer.public();
+        EnodeAddr::new(public,SocketAddr::new(ip,self.network_addr.port()))
+    }
+
+    /// Public (e.g. NAT) address, if distinct from the network address.
+    pub fn public_addr(&self) -> Option<SocketAddr> {
+        self.public_addr
+    }
+
+    pub fn iter_alt_addrs(&self) -> impl Iterator<Item=&SocketAddr> {
+        self.alt_addrs.iter()
+    }
+
+    /// Enode addresses advertised to peers outside of the project's network.
+    pub fn public_enodes(&self) -> Vec<EnodeAddr> {
+        let public = self.network_signer.public();
+        Some(self.public_addr.unwrap_or(self.network_addr)).into_iter()
+            .chain(self.iter_alt_addrs().cloned())
+            .map(|addr| EnodeAddr::new(public,addr))
+            .collect()
+    }
 }
 
 
@@ -278,6 +317,21 @@ pub struct ExternalNodeConfig {
 
 
 /// Internally defined node 
+///
+/// The `network-addr` is the node's primary address, used by other internal nodes.
+/// Nodes behind NAT may also specify a `public-addr` (and any number of `alt-addrs`),
+/// which are advertised to external nodes instead.  Parity itself advertises the
+/// `public-addr` ip on the `network-addr` port, so the public port should match it.
+///
+/// ```toml
+/// [[node.internal]]
+/// node-name = "node-0"
+/// node-role = "authority"
+/// network-addr = "10.0.0.2:30303"
+/// public-addr = "203.0.113.7:30303"
+/// alt-addrs = ["198.51.100.7:30303"]
+/// ```
+///
 #[derive(Debug,Clone,Serialize,Deserialize)]
 #[serde(rename_all = "kebab-case",deny_unknown_fields)]
 pub struct InternalNodeConfig {
@@ -288,7 +342,11 @@ pub struct InternalNodeConfig {
     account_pass: Option<String>,
     #[serde(default,skip_serializing_if = "Option::is_none")]
     account_key: Option<Secret>,
-    network_addr: SocketAddrV4,
+    network_addr: SocketAddr,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    public_addr: Option<SocketAddr>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    alt_addrs: Option<Vec<SocketAddr>>,
     #[serde(default,skip_serializing_if = "Option::is_none")]
     network_key: Option<Secret>,
     #[serde(default,skip_serializing_if = "Option::is_none")]
@@ -317,11 +375,13 @@ impl Default for NodeRole {
 
 impl InternalNodeConfig {
 
-    pub fn new(name: String, address: SocketAddrV4, role: NodeRole) -> Self {
+    pub fn new(name: String, address: SocketAddr, role: NodeRole) -> Self {
         Self {
             node_name: name,
             node_role: role,
             network_addr: address,
+            public_addr: None,
+            alt_addrs: None,
             account_pass: None,
             account_key: None,
             network_key: None,
@@ -331,11 +391,11 @@ impl InternalNodeConfig {
         }
     }
 
-    pub fn authority(name: String, address: SocketAddrV4) -> Self {
+    pub fn authority(name: String, address: SocketAddr) -> Self {
         Self::new(name,address,NodeRole::Authority)
     }
 
-    pub fn interface(name: String, address: SocketAddrV4) -> Self {
+    pub fn interface(name: String, address: SocketAddr) -> Self {
         Self::new(name,address,NodeRole::Interface)
     }
 }
@@ -0,0 +1,228 @@
+//! Operational settings of compose services.
+//!
+use project::node::{Node,NodeRole};
+use std::collections::BTreeMap;
+use std::fmt;
+
+
+/// Operational settings of the compose services of all nodes
+///
+/// ```toml
+/// [docker-compose.services.all]
+/// restart = "unless-stopped"
+/// healthcheck = true
+/// logging = { driver = "json-file", options = { max-size = "10m" } }
+/// labels = { team = "infra" }
+///
+/// [docker-compose.services.authority]
+/// memory = "2g"
+/// cpus = 1.5
+///
+/// [docker-compose.services.interface]
+/// depends-on = ["authority"]
+///
+/// [docker-compose.services.node.node-3]
+/// restart = "no"
+/// ```
+///
+/// Settings of a node override those of its role, which override those of `all`.
+///
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct ServicesConfig {
+    /// Settings of every node
+    #[serde(default)]
+    pub all: ServiceOptions,
+
+    /// Settings of authority nodes
+    #[serde(default)]
+    pub authority: ServiceOptions,
+
+    /// Settings of interface nodes
+    #[serde(default)]
+    pub interface: ServiceOptions,
+
+    /// Settings of individual nodes, by name
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub node: BTreeMap<String,ServiceOptions>,
+
+    /// Parameters of the JSON-RPC healthcheck
+    #[serde(default)]
+    pub healthcheck: HealthcheckConfig,
+}
+
+
+impl ServicesConfig {
+
+    /// Combined settings of `node`.
+    pub fn options(&self, node: &Node) -> ServiceOptions {
+        let mut options = self.all.clone();
+        match node.node_role() {
+            NodeRole::Authority => options.merge(&self.authority),
+            NodeRole::Interface => options.merge(&self.interface),
+        }
+        if let Some(node_options) = self.node.get(node.name()) {
+            options.merge(node_options);
+        }
+        options
+    }
+}
+
+
+/// Operational settings of a compose service
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct ServiceOptions {
+    /// Restart policy (`no`, `always`, `on-failure` or `unless-stopped`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RestartPolicy>,
+
+    /// Check the health of the node via its JSON-RPC server
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<bool>,
+
+    /// Memory limit (e.g. `512m` or `2g`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+
+    /// Limit on the number of CPUs used
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+
+    /// Logging driver & its options
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingConfig>,
+
+    /// Container labels (merged with any less specific labels)
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String,String>,
+
+    /// Nodes which must be up (or healthy, if healthchecked) before this node is started,
+    /// given by name, role or tag
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+}
+
+
+impl ServiceOptions {
+
+    /// Override these settings with any settings of `other`.
+    pub fn merge(&mut self, other: &Self) {
+        if other.restart.is_some() { self.restart = other.restart; }
+        if other.healthcheck.is_some() { self.healthcheck = other.healthcheck; }
+        if other.memory.is_some() { self.memory = other.memory.clone(); }
+        if other.cpus.is_some() { self.cpus = other.cpus; }
+        if other.logging.is_some() { self.logging = other.logging.clone(); }
+        if other.depends_on.is_some() { self.depends_on = other.depends_on.clone(); }
+        self.labels.extend(other.labels.iter().map(|(k,v)| (k.clone(),v.clone())));
+    }
+
+    pub fn is_healthchecked(&self) -> bool { self.healthcheck.unwrap_or(false) }
+
+    /// Check whether `node` is selected by any `depends-on` entry.
+    pub fn depends_on(&self, node: &Node) -> bool {
+        let role = match node.node_role() {
+            NodeRole::Authority => "authority",
+            NodeRole::Interface => "interface",
+        };
+        self.depends_on.as_ref().map(|selectors| selectors.iter().any(|selector| {
+            selector == node.name() || selector == role || node.tags().contains(selector)
+        })).unwrap_or(false)
+    }
+}
+
+
+/// Restart policy of a compose service
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    No,
+    Always,
+    OnFailure,
+    UnlessStopped,
+}
+
+
+impl fmt::Display for RestartPolicy {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RestartPolicy::No => f.write_str("no"),
+            RestartPolicy::Always => f.write_str("always"),
+            RestartPolicy::OnFailure => f.write_str("on-failure"),
+            RestartPolicy::UnlessStopped => f.write_str("unless-stopped"),
+        }
+    }
+}
+
+
+/// Logging driver of a compose service
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub driver: String,
+    #[serde(default,skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String,String>,
+}
+
+
+/// Parameters of the JSON-RPC healthcheck
+///
+/// ```toml
+/// [docker-compose.services.healthcheck]
+/// method = "eth_blockNumber"
+/// interval = "15s"
+/// retries = 10
+/// ```
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct HealthcheckConfig {
+    /// JSON-RPC method called (w/o params) to check the node
+    #[serde(default = "default_method")]
+    pub method: String,
+
+    #[serde(default = "default_interval")]
+    pub interval: String,
+
+    #[serde(default = "default_timeout")]
+    pub timeout: String,
+
+    /// Consecutive failures after which the node is unhealthy
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+
+impl Default for HealthcheckConfig {
+
+    fn default() -> Self {
+        Self {
+            method: default_method(),
+            interval: default_interval(),
+            timeout: default_timeout(),
+            retries: default_retries(),
+        }
+    }
+}
+
+
+fn default_method() -> String { "net_version".into() }
+
+fn default_interval() -> String { "10s".into() }
+
+fn default_timeout() -> String { "5s".into() }
+
+fn default_retries() -> u32 { 5 }
+
+
+impl HealthcheckConfig {
+
+    /// Shell command which succeeds if the JSON-RPC server at `port` answers `method` (requires
+    /// `curl` in the image).
+    pub fn command(&self, port: u16) -> String {
+        let request = json!({ "jsonrpc": "2.0", "method": self.method, "params": [], "id": 1 });
+        format!("curl -sf -H 'Content-Type: application/json' --data '{}' http://localhost:{} | grep -q result",
+            request,port)
+    }
+}
@@ -0,0 +1,280 @@
+//! Pre-computed contract storage for genesis accounts.
+//!
+//! Slots are computed according to solidity's storage layout, allowing
+//! contracts to be inserted at genesis in their initialized state rather
+//! than via a constructor.
+use mimir_crypto::secp256k1::Address;
+use serde_json::{Map,Value};
+use tiny_keccak;
+use util;
+use std::collections::BTreeMap;
+
+
+/// A 32-byte storage slot (or value)
+pub type Word = [u8;32];
+
+
+/// Storage of a genesis contract
+#[derive(Default,Debug,Clone)]
+pub struct Storage {
+    inner: BTreeMap<Word,Word>,
+}
+
+
+impl Storage {
+
+    pub fn insert(&mut self, slot: Word, value: Word) {
+        self.inner.insert(slot,value);
+    }
+
+    pub fn set_address(&mut self, slot: Word, addr: Address) {
+        self.insert(slot,address_word(addr));
+    }
+
+    pub fn set_uint(&mut self, slot: Word, value: u64) {
+        self.insert(slot,uint_word(value));
+    }
+
+    /// Store a `string` (or `bytes`) value, packing short values into a single slot.
+    pub fn set_string(&mut self, slot: Word, value: &str) {
+        let data = value.as_bytes();
+        if data.len() < 32 {
+            let mut word = [0u8;32];
+            word[..data.len()].copy_from_slice(data);
+            word[31] = (data.len() * 2) as u8;
+            self.insert(slot,word);
+        } else {
+            self.set_uint(slot,data.len() as u64 * 2 + 1);
+            let start = keccak(&slot);
+            for (index,chunk) in data.chunks(32).enumerate() {
+                let mut word = [0u8;32];
+                word[..chunk.len()].copy_from_slice(chunk);
+                self.insert(offset(start,index as u64),word);
+            }
+        }
+    }
+
+    /// Store the elements of a dynamic array of single-slot values.
+    pub fn set_array(&mut self, slot: Word, values: &[Word]) {
+        self.set_uint(slot,values.len() as u64);
+        let start = keccak(&slot);
+        for (index,value) in values.iter().enumerate() {
+            self.insert(offset(start,index as u64),*value);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.inner.is_empty() }
+
+    pub fn iter(&self) -> impl Iterator<Item=(&Word,&Word)> {
+        self.inner.iter()
+    }
+
+    /// Storage in the form expected by parity's chain specification.
+    pub fn to_json(&self) -> Value {
+        let entries = self.inner.iter().map(|(slot,value)| {
+            (hex_word(slot),Value::String(hex_word(value)))
+        }).collect::<Map<_,_>>();
+        Value::Object(entries)
+    }
+}
+
+
+/// Slot of the state variable at position `index`.
+pub fn slot(index: u64) -> Word { uint_word(index) }
+
+
+/// Slot of the mapping entry for `key` within the mapping at `base`.
+///
+/// Value-type keys must be padded to 32 bytes (e.g. via `address_word`), while
+/// `string` and `bytes` keys are used as-is.
+pub fn mapping_slot(base: Word, key: &[u8]) -> Word {
+    let mut preimage = key.to_vec();
+    preimage.extend_from_slice(&base);
+    keccak(&preimage)
+}
+
+
+pub fn address_word(addr: Address) -> Word {
+    let mut word = [0u8;32];
+    word[12..].copy_from_slice(&addr.into_inner());
+    word
+}
+
+
+pub fn uint_word(value: u64) -> Word {
+    let mut word = [0u8;32];
+    for (index,byte) in word[24..].iter_mut().enumerate() {
+        *byte = (value >> (8 * (7 - index))) as u8;
+    }
+    word
+}
+
+
+/// Parse a non-negative decimal integer.
+pub fn parse_uint(decimal: &str) -> Option<Word> {
+    if decimal.is_empty() {
+        return None;
+    }
+    let mut word = [0u8;32];
+    for digit in decimal.chars() {
+        let mut carry = digit.to_digit(10)?;
+        for byte in word.iter_mut().rev() {
+            let product = *byte as u32 * 10 + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(word)
+}
+
+
+/// Format a word as a decimal integer.
+pub fn format_uint(word: Word) -> String {
+    let mut word = word;
+    let mut digits = Vec::new();
+    loop {
+        let mut remainder = 0u32;
+        for byte in word.iter_mut() {
+            let value = (remainder << 8) | *byte as u32;
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+        if word.iter().all(|byte| *byte == 0) {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("always valid UTF-8")
+}
+
+
+/// Sum of two words (`None` on overflow).
+pub fn add_words(a: Word, b: Word) -> Option<Word> {
+    let mut sum = [0u8;32];
+    let mut carry = 0u16;
+    for index in (0..32).rev() {
+        let total = a[index] as u16 + b[index] as u16 + carry;
+        sum[index] = total as u8;
+        carry = total >> 8;
+    }
+    if carry == 0 { Some(sum) } else { None }
+}
+
+
+/// Slot `count` positions after `slot`.
+pub fn offset(slot: Word, count: u64) -> Word {
+    let mut word = slot;
+    let mut carry = count as u128;
+    for byte in word.iter_mut().rev() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *byte as u128 + (carry & 0xff);
+        *byte = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+    word
+}
+
+
+pub fn keccak(data: &[u8]) -> Word { tiny_keccak::keccak256(data) }
+
+
+fn hex_word(word: &Word) -> String { format!("0x{}",util::hex_string(word)) }
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::Address;
+    use util;
+    use super::*;
+
+    /// Maximum `uint256` value.
+    const UINT_MAX: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+    fn hex(word: &Word) -> String { util::hex_string(word) }
+
+    fn stored(state: &Storage) -> Vec<(String,String)> {
+        state.iter().map(|(slot,value)| (hex(slot),hex(value))).collect()
+    }
+
+    #[test]
+    fn state_variable_slots() {
+        assert_eq!(slot(0),[0u8;32]);
+        assert_eq!(hex(&slot(5)),format!("{:064x}",5));
+        assert_eq!(hex(&slot(0x0102)),format!("{:064x}",0x0102));
+    }
+
+    #[test]
+    fn mapping_slots() {
+        // `mapping(uint256 => ...)` at slot 0, key 0.
+        assert_eq!(hex(&mapping_slot(slot(0),&[0u8;32])),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5");
+        // `mapping(address => ...)` at slot 0.
+        let addr: Address = "0x00000000000000000000000000000000000a11ce".parse().unwrap();
+        assert_eq!(hex(&mapping_slot(slot(0),&address_word(addr))),
+            "9ee878158fd03ad640d4a0705a30c697ca9fe067e51ec5dde8bf6b55f41c298c");
+        // `mapping(string => ...)` at slot 1 (keys are unpadded).
+        assert_eq!(hex(&mapping_slot(slot(1),b"node-0")),
+            "588085be07aac4ff2b71a5f960bc15c87e1f8b9c63dd4af901be523ee8f3e753");
+    }
+
+    #[test]
+    fn word_arithmetic() {
+        assert_eq!(add_words(uint_word(1),uint_word(2)),Some(uint_word(3)));
+        assert_eq!(add_words(uint_word(0xff),uint_word(1)),Some(uint_word(0x100)));
+        let max = parse_uint(UINT_MAX).unwrap();
+        assert_eq!(max,[0xff;32]);
+        assert_eq!(add_words(max,uint_word(0)),Some(max));
+        assert_eq!(add_words(max,uint_word(1)),None);
+        let start = keccak(&slot(0));
+        assert_eq!(hex(&offset(start,1)),"290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e564");
+        assert_eq!(offset(uint_word(0xff),1),uint_word(0x100));
+    }
+
+    #[test]
+    fn decimal_conversion() {
+        assert_eq!(parse_uint("0"),Some([0u8;32]));
+        assert_eq!(parse_uint("1000000000000000000"),Some(uint_word(1_000_000_000_000_000_000)));
+        assert_eq!(format_uint(uint_word(0)),"0");
+        assert_eq!(format_uint(uint_word(1_000_000_000_000_000_000)),"1000000000000000000");
+        assert_eq!(format_uint([0xff;32]),UINT_MAX);
+        let mut overflow = UINT_MAX.to_owned();
+        overflow.pop();
+        overflow.push('6');
+        assert_eq!(parse_uint(&overflow),None);
+        assert_eq!(parse_uint(""),None);
+        assert_eq!(parse_uint("12a"),None);
+        assert_eq!(parse_uint("-1"),None);
+    }
+
+    #[test]
+    fn short_strings_are_packed() {
+        let mut state = Storage::default();
+        state.set_string(slot(3),"abc");
+        let expected = format!("616263{}06",&"0".repeat(56));
+        assert_eq!(stored(&state),vec![(hex(&slot(3)),expected)]);
+        let mut state = Storage::default();
+        state.set_string(slot(3),&"a".repeat(31));
+        let expected = format!("{}3e","61".repeat(31));
+        assert_eq!(stored(&state),vec![(hex(&slot(3)),expected)]);
+    }
+
+    #[test]
+    fn long_strings_are_chunked() {
+        let mut state = Storage::default();
+        state.set_string(slot(0),&"a".repeat(40));
+        let start = keccak(&slot(0));
+        let mut expected = vec![
+            (hex(&slot(0)),format!("{:064x}",40 * 2 + 1)),
+            (hex(&start),"61".repeat(32)),
+            (hex(&offset(start,1)),format!("{}{}","61".repeat(8),"0".repeat(48))),
+        ];
+        expected.sort();
+        assert_eq!(stored(&state),expected);
+    }
+}
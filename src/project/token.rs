@@ -0,0 +1,305 @@
+//! ERC-20 tokens with balances allocated at genesis.
+use mimir_crypto::secp256k1::Address;
+use project::Project;
+use project::bundled;
+use project::contract::{ContractConfig,ContractArgument};
+use project::compiler::CompilerKind;
+use project::storage::{self,Storage,Word};
+use types::{Tags,Error};
+use std::collections::{BTreeMap,HashMap};
+
+
+/// Number of decimals used unless otherwise specified
+pub const DEFAULT_DECIMALS: u8 = 18;
+
+
+/// A token contract whose balances are allocated at genesis
+///
+/// ```toml
+/// [[token]]
+/// name = "PibToken"
+/// addr = "0x0000000000000000000000000000000000002000"
+/// token-name = "Pib Token"
+/// symbol = "PIB"
+///
+/// [token.allocations]
+/// "match-addrs::customer" = "1000"
+/// "account-addr::actor-0" = "2.5"
+/// ```
+///
+/// Unless a `source` is given, the bundled `StandardToken` is used.  User-supplied
+/// contracts must either share its storage layout, or describe their own via `layout`.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",deny_unknown_fields)]
+pub struct TokenConfig {
+    /// Name of the token's contract entry
+    pub name: String,
+
+    /// Address of the token
+    pub addr: Address,
+
+    /// Source file of a user-supplied token contract
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Name of the contract within `source` (defaults to `name`)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+
+    /// Token name, as returned by `name()`
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub token_name: Option<String>,
+
+    /// Token symbol, as returned by `symbol()`
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+
+    /// Number of decimals; allocations are specified in whole tokens
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+
+    /// Amounts allocated to the addresses represented by each argument
+    #[serde(default)]
+    pub allocations: BTreeMap<String,String>,
+
+    /// Storage layout of the token contract
+    #[serde(default)]
+    pub layout: TokenLayout,
+
+    /// Arbitrary tags
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+
+fn default_decimals() -> u8 { DEFAULT_DECIMALS }
+
+
+/// Storage slots of the token's state variables (defaults match `StandardToken`)
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case",default,deny_unknown_fields)]
+pub struct TokenLayout {
+    /// `mapping(address => uint256)` of balances
+    pub balances: u64,
+    /// `uint256` total supply
+    pub total_supply: u64,
+    /// `string` token name
+    pub name: u64,
+    /// `string` token symbol
+    pub symbol: u64,
+    /// `uint8` decimals
+    pub decimals: u64,
+}
+
+
+impl Default for TokenLayout {
+
+    fn default() -> Self {
+        Self { balances: 0, total_supply: 2, name: 3, symbol: 4, decimals: 5 }
+    }
+}
+
+
+impl TokenConfig {
+
+    pub fn contract_config(&self) -> ContractConfig {
+        let mut config = ContractConfig::new(self.name.clone(),self.addr);
+        match self.source.as_ref() {
+            Some(source) => {
+                config.source = Some(source.to_owned());
+                config.contract = self.contract.clone();
+            },
+            None => {
+                config.source = Some(format!("{}/{}",bundled::BUNDLED_DIR,bundled::TOKEN_FILENAME));
+                config.contract = Some(bundled::TOKEN_NAME.to_owned());
+                config.compiler = Some(CompilerKind::Solc);
+            },
+        }
+        config.tags = self.tags.clone();
+        config
+    }
+
+    /// Compute the genesis storage of the token.
+    pub fn storage(&self, project: &Project) -> Result<Storage,Error> {
+        let mut balances: HashMap<Address,Word> = HashMap::new();
+        for (target,amount) in self.allocations.iter() {
+            let argument: ContractArgument = target.parse()?;
+            let amount = self.base_units(amount)?;
+            let addrs = argument.resolve_addrs(project)?;
+            if addrs.is_empty() {
+                warn!("allocation `{}` of `{}` matches no accounts",target,self.name);
+            }
+            for addr in addrs {
+                let balance = balances.entry(addr).or_insert([0u8;32]);
+                *balance = self.checked_add(*balance,amount)?;
+            }
+        }
+        let layout = &self.layout;
+        let mut state = Storage::default();
+        let mut total_supply = [0u8;32];
+        for (addr,balance) in balances.iter() {
+            let slot = storage::mapping_slot(storage::slot(layout.balances),&storage::address_word(*addr));
+            state.insert(slot,*balance);
+            total_supply = self.checked_add(total_supply,*balance)?;
+        }
+        state.insert(storage::slot(layout.total_supply),total_supply);
+        if let Some(name) = self.token_name.as_ref() {
+            state.set_string(storage::slot(layout.name),name);
+        }
+        if let Some(symbol) = self.symbol.as_ref() {
+            state.set_string(storage::slot(layout.symbol),symbol);
+        }
+        state.set_uint(storage::slot(layout.decimals),self.decimals as u64);
+        Ok(state)
+    }
+
+    /// Convert an amount of whole tokens (e.g. `"2.5"`) into base units.
+    fn base_units(&self, amount: &str) -> Result<Word,Error> {
+        let decimals = self.decimals as usize;
+        let amount = amount.trim();
+        let (whole,fraction) = match amount.find('.') {
+            Some(index) => (&amount[..index],&amount[index + 1..]),
+            None => (amount,""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(Error::message(format!("invalid amount `{}` for `{}`",amount,self.name)));
+        }
+        if fraction.len() > decimals {
+            let msg = format!("amount `{}` of `{}` exceeds {} decimals",amount,self.name,decimals);
+            return Err(Error::message(msg));
+        }
+        let digits = format!("{}{}{}",whole,fraction,"0".repeat(decimals - fraction.len()));
+        storage::parse_uint(&digits).ok_or_else(|| {
+            Error::message(format!("invalid amount `{}` for `{}`",amount,self.name))
+        })
+    }
+
+    fn checked_add(&self, a: Word, b: Word) -> Result<Word,Error> {
+        storage::add_words(a,b).ok_or_else(|| {
+            Error::message(format!("total supply of `{}` overflows",self.name))
+        })
+    }
+}
+
+
+#[derive(Default,Debug,Clone,Serialize,Deserialize)]
+pub struct TokenConfigs(Vec<TokenConfig>);
+
+
+impl TokenConfigs {
+
+    pub fn import(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    pub fn iter(&self) -> impl Iterator<Item=&TokenConfig> {
+        self.0.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use project::storage::{self,Word};
+    use project::tests::project;
+    use util;
+    use toml;
+    use super::*;
+
+    const TOKEN: &str = r#"
+        name = "PibToken"
+        addr = "0x0000000000000000000000000000000000002000"
+        token-name = "Pib Token"
+        symbol = "PIB"
+    "#;
+
+    const NODES: &str = r#"
+        [[internal]]
+        node-name = "node-0"
+        network-addr = "10.0.0.2:30303"
+        tags = ["holder"]
+
+        [[internal]]
+        node-name = "node-1"
+        network-addr = "10.0.0.3:30303"
+        tags = ["holder"]
+    "#;
+
+    /// Both nodes receive 1.5 tokens, & `node-0` one more, under a non-standard layout.
+    const ALLOCATED: &str = r#"
+        decimals = 2
+
+        [layout]
+        balances = 7
+        total-supply = 1
+
+        [allocations]
+        "match-addrs::holder" = "1.5"
+        "account-addr::node-0" = "1"
+    "#;
+
+    fn token(extra: &str) -> TokenConfig {
+        toml::from_str(&format!("{}{}",TOKEN,extra)).unwrap()
+    }
+
+    fn units(token: &TokenConfig, amount: &str) -> String {
+        storage::format_uint(token.base_units(amount).unwrap())
+    }
+
+    #[test]
+    fn whole_and_fractional_amounts() {
+        let config = token("");
+        assert_eq!(config.decimals,DEFAULT_DECIMALS);
+        assert_eq!(units(&config,"1000"),"1000000000000000000000");
+        assert_eq!(units(&config,"2.5"),"2500000000000000000");
+        assert_eq!(units(&config," 0.000000000000000001 "),"1");
+        assert_eq!(units(&config,".5"),"500000000000000000");
+        assert_eq!(units(&config,"3."),"3000000000000000000");
+        let config = token("decimals = 0");
+        assert_eq!(units(&config,"42"),"42");
+    }
+
+    #[test]
+    fn invalid_amounts() {
+        let config = token("decimals = 2");
+        for amount in &["","."," ","1.234","-1","1e3","1.2.3"] {
+            assert!(config.base_units(amount).is_err(),"amount `{}` must be rejected",amount);
+        }
+        let config = token("");
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert!(config.base_units(max).is_err());
+    }
+
+    #[test]
+    fn layout_defaults_match_standard_token() {
+        let layout = token("").layout;
+        let slots = (layout.balances,layout.total_supply,layout.name,layout.symbol,layout.decimals);
+        assert_eq!(slots,(0,2,3,4,5));
+        let layout = token("[layout]\nbalances = 7\nsymbol = 8\n").layout;
+        let slots = (layout.balances,layout.total_supply,layout.name,layout.symbol,layout.decimals);
+        assert_eq!(slots,(7,2,3,8,5));
+    }
+
+    #[test]
+    fn storage_follows_layout() {
+        let project = project(NODES,"","");
+        let config = token(ALLOCATED);
+        let state = config.storage(&project).unwrap();
+        let stored: Vec<(Word,Word)> = state.iter().map(|(slot,value)| (*slot,*value)).collect();
+        let load = |slot: Word| stored.iter().find(|(key,_)| *key == slot).map(|(_,value)| *value);
+        for node in project.iter_nodes() {
+            let slot = storage::mapping_slot(storage::slot(7),&storage::address_word(node.account_addr()));
+            let expected = if node.name() == "node-0" { 250 } else { 150 };
+            assert_eq!(load(slot),Some(storage::uint_word(expected)));
+        }
+        assert_eq!(load(storage::slot(1)),Some(storage::uint_word(400)));
+        let name = load(storage::slot(3)).unwrap();
+        assert_eq!(util::hex_string(&name[..10]),util::hex_string(b"Pib Token\0"));
+        assert_eq!(name[31],18);
+        assert_eq!(load(storage::slot(5)),Some(storage::uint_word(2)));
+        assert_eq!(stored.len(),6);
+    }
+}
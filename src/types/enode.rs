@@ -1,7 +1,7 @@
 use serde::de::{self,Deserialize,Deserializer};
 use serde::ser::{Serialize,Serializer};
 use mimir_crypto::secp256k1::Public;
-use std::net::SocketAddrV4;
+use std::net::{IpAddr,Ipv6Addr,SocketAddr};
 use std::str::FromStr;
 use std::{fmt,error};
 use hex;
@@ -9,6 +9,8 @@ use hex;
 
 const PREFIX: &'static str = "enode://";
 
+const DISCPORT: &'static str = "discport=";
+
 /// ethereum enode address
 /// 
 /// ```
@@ -26,20 +28,53 @@ const PREFIX: &'static str = "enode://";
 /// # }
 /// ```
 ///
-#[derive(Debug,Copy,Clone)]
+/// IPv6 addresses, hostnames and a distinct discovery port are also supported:
+///
+/// ```
+/// extern crate pib;
+/// 
+/// use pib::types::EnodeAddr;
+///
+/// # fn main() {
+/// 
+///  let key = "39198951bcf039efa518a0b87e9ea8c9e0eca0e58a8d786c672d83bfb8f7afe0263998ed73411a0004345aaaafab7975c3baea857064c63c004f84ae28001308";
+///
+///  for host in &["[fd00::1e]:30303","node-0.partner.example:30303?discport=30301"] {
+///      let raw = format!("enode://{}@{}",key,host);
+///      let enode: EnodeAddr = raw.parse().unwrap();
+///      assert_eq!(raw,enode.to_string());
+///  }
+/// # }
+/// ```
+///
+#[derive(Debug,Clone)]
 pub struct EnodeAddr {
     /// public (network) key
     pub public: Public,
 
-    /// external address
-    pub addr: SocketAddrV4
+    /// external host (ip address or hostname)
+    pub host: Host,
+
+    /// tcp (listening) port
+    pub port: u16,
+
+    /// udp (discovery) port, if distinct from `port`
+    pub discport: Option<u16>,
 }
 
 
 impl EnodeAddr {
 
-    pub fn new(public: Public, addr: SocketAddrV4) -> Self {
-        Self { public, addr }
+    pub fn new(public: Public, addr: SocketAddr) -> Self {
+        Self { public, host: Host::Ip(addr.ip()), port: addr.port(), discport: None }
+    }
+
+    /// Socket address of the node (if its host is an ip address).
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self.host {
+            Host::Ip(ip) => Some(SocketAddr::new(ip,self.port)),
+            Host::Domain(_) => None,
+        }
     }
 }
 
@@ -53,12 +88,16 @@ impl fmt::Display for EnodeAddr {
         let hex_str = hex::as_str(&self.public,&mut hex_buf);
         f.write_str(PREFIX)?;
         f.write_str(hex_str)?;
-        f.write_str("@")?;
-        self.addr.fmt(f)
+        write!(f,"@{}:{}",self.host,self.port)?;
+        if let Some(discport) = self.discport {
+            write!(f,"?{}{}",DISCPORT,discport)?;
+        }
+        Ok(())
     }
 }
 
 
+
 impl FromStr for EnodeAddr {
 
     type Err = ParseEnodeError;
@@ -77,13 +116,85 @@ impl FromStr for EnodeAddr {
         };
         let public: Public = key_str.parse()
             .map_err(|_| ParseEnodeError)?;
-        let addr: SocketAddrV4 = addr_str.parse()
+        let (addr_str,discport) = match addr_str.find('?') {
+            Some(index) => {
+                let query = &addr_str[index + 1..];
+                if !query.starts_with(DISCPORT) {
+                    return Err(ParseEnodeError);
+                }
+                let discport = query[DISCPORT.len()..].parse()
+                    .map_err(|_| ParseEnodeError)?;
+                (&addr_str[..index],Some(discport))
+            },
+            None => (addr_str,None),
+        };
+        let index = addr_str.rfind(':').ok_or(ParseEnodeError)?;
+        let host = addr_str[..index].parse()?;
+        let port = addr_str[index + 1..].parse()
             .map_err(|_| ParseEnodeError)?;
-        Ok(Self { public, addr })
+        Ok(Self { public, host, port, discport })
     }
 }
 
 
+/// host portion of an enode address
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Host {
+    /// literal ipv4 or ipv6 address
+    Ip(IpAddr),
+    /// dns name
+    Domain(String),
+}
+
+
+impl fmt::Display for Host {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Host::Ip(IpAddr::V4(ip)) => ip.fmt(f),
+            Host::Ip(IpAddr::V6(ip)) => write!(f,"[{}]",ip),
+            Host::Domain(name) => f.write_str(name),
+        }
+    }
+}
+
+
+impl FromStr for Host {
+
+    type Err = ParseEnodeError;
+
+    /// Parse a host, expecting ipv6 addresses to be enclosed in brackets.
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        if s.starts_with('[') && s.ends_with(']') {
+            let ip: Ipv6Addr = s[1..s.len() - 1].parse()
+                .map_err(|_| ParseEnodeError)?;
+            Ok(Host::Ip(ip.into()))
+        } else if let Ok(ip) = s.parse::<IpAddr>() {
+            // unbracketed ipv6 addresses are ambiguous w/ the port separator
+            if ip.is_ipv4() { Ok(Host::Ip(ip)) } else { Err(ParseEnodeError) }
+        } else if is_hostname(s) {
+            Ok(Host::Domain(s.to_owned()))
+        } else {
+            Err(ParseEnodeError)
+        }
+    }
+}
+
+
+/// Check for a valid dns name.  The final label may not be all-numeric, so that malformed
+/// ipv4 addresses (e.g. `10.0.0.256`) are not mistaken for domains.
+fn is_hostname(s: &str) -> bool {
+    let numeric_tld = s.rsplit('.').next()
+        .map(|label| label.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false);
+    !s.is_empty() && s.len() <= 253 && !numeric_tld && s.split('.').all(|label| {
+        !label.is_empty() && label.len() <= 63
+            && !label.starts_with('-') && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+
 /// error during enode parsing
 #[derive(Debug,Copy,Clone)]
 pub struct ParseEnodeError;
@@ -3,10 +3,14 @@ mod include;
 mod enode;
 mod error;
 mod files;
+mod transaction;
+mod subnet;
 
 pub use self::include::Include;
-pub use self::enode::{EnodeAddr,ParseEnodeError};
+pub use self::enode::{EnodeAddr,Host,ParseEnodeError};
 pub use self::error::Error;
+pub use self::subnet::Subnet;
+pub use self::transaction::{Transaction,SignedTransaction,contract_address};
 pub use self::files::{
     ProjectFiles,
     SetupFiles,
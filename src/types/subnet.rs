@@ -0,0 +1,195 @@
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer};
+use types::Error;
+use util;
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
+use std::str::FromStr;
+use std::fmt;
+
+
+/// ip subnet in CIDR notation
+///
+/// ```
+/// extern crate pib;
+///
+/// use pib::types::Subnet;
+///
+/// # fn main() {
+///
+///  let subnet: Subnet = "172.28.0.0/16".parse().unwrap();
+///
+///  assert!(subnet.contains("172.28.4.2".parse().unwrap()));
+///  assert!(!subnet.contains("172.29.0.1".parse().unwrap()));
+///  assert_eq!(subnet.gateway().to_string(),"172.28.0.1");
+/// # }
+/// ```
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Subnet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+
+impl Subnet {
+
+    /// Subnet of length `prefix` containing `addr` (host bits are cleared).
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self,Error> {
+        if prefix > max_prefix(addr) {
+            return Err(Error::message(format!("invalid prefix length `{}` for `{}`",prefix,addr)));
+        }
+        let subnet = Self { addr, prefix };
+        let addr = from_bits(addr,to_bits(addr) & subnet.mask());
+        Ok(Self { addr, prefix })
+    }
+
+    /// Network (first) address of the subnet.
+    pub fn network(&self) -> IpAddr { self.addr }
+
+    pub fn prefix(&self) -> u8 { self.prefix }
+
+    pub fn is_ipv4(&self) -> bool { self.addr.is_ipv4() }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.is_ipv4() && to_bits(addr) & self.mask() == to_bits(self.addr)
+    }
+
+    /// Whether the two subnets share any address.
+    pub fn overlaps(&self, other: &Subnet) -> bool {
+        self.contains(other.addr) || other.contains(self.addr)
+    }
+
+    /// Whether `addr` is usable by a host (i.e. is neither the network nor the broadcast address).
+    pub fn is_host(&self, addr: IpAddr) -> bool {
+        self.contains(addr) && addr != self.addr && !(self.is_ipv4() && addr == self.broadcast())
+    }
+
+    /// Conventional gateway (first host address) of the subnet.
+    pub fn gateway(&self) -> IpAddr {
+        from_bits(self.addr,to_bits(self.addr) + 1)
+    }
+
+    /// All host addresses of the subnet, in ascending order.
+    pub fn hosts(&self) -> impl Iterator<Item=IpAddr> {
+        let subnet = *self;
+        let first = to_bits(self.addr) + 1;
+        let last = to_bits(self.broadcast());
+        (first..=last).map(move |bits| from_bits(subnet.addr,bits))
+            .filter(move |addr| subnet.is_host(*addr))
+    }
+
+    fn broadcast(&self) -> IpAddr {
+        from_bits(self.addr,to_bits(self.addr) | (host_bits(self.addr) & !self.mask()))
+    }
+
+    fn mask(&self) -> u128 {
+        let host = (max_prefix(self.addr) - self.prefix) as u32;
+        let host_mask = 1u128.checked_shl(host).map(|bit| bit - 1).unwrap_or(u128::max_value());
+        host_bits(self.addr) & !host_mask
+    }
+}
+
+
+fn max_prefix(addr: IpAddr) -> u8 {
+    if addr.is_ipv4() { 32 } else { 128 }
+}
+
+
+fn host_bits(addr: IpAddr) -> u128 {
+    if addr.is_ipv4() { u32::max_value() as u128 } else { u128::max_value() }
+}
+
+
+fn to_bits(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(ip) => u32::from(ip) as u128,
+        IpAddr::V6(ip) => u128::from(ip),
+    }
+}
+
+
+fn from_bits(family: IpAddr, bits: u128) -> IpAddr {
+    match family {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(bits as u32)),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(bits)),
+    }
+}
+
+
+impl fmt::Display for Subnet {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}/{}",self.addr,self.prefix)
+    }
+}
+
+
+impl FromStr for Subnet {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        let invalid = || Error::message(format!("invalid subnet `{}` (expected e.g. `172.28.0.0/16`)",s));
+        let mut split = s.trim().splitn(2,'/');
+        match (split.next(),split.next()) {
+            (Some(addr),Some(prefix)) => {
+                let addr = addr.parse().map_err(|_| invalid())?;
+                let prefix = prefix.parse().map_err(|_| invalid())?;
+                Self::new(addr,prefix)
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+
+impl Serialize for Subnet {
+
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok,S::Error> where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Subnet {
+
+    fn deserialize<D>(deserializer: D) -> Result<Self,D::Error> where D: Deserializer<'de> {
+        let target: util::Either<&str,String> = Deserialize::deserialize(deserializer)?;
+        let subnet: &str = target.as_ref();
+        subnet.parse().map_err(de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use super::Subnet;
+
+    fn hosts(subnet: &str) -> Vec<String> {
+        let subnet: Subnet = subnet.parse().unwrap();
+        subnet.hosts().map(|addr| addr.to_string()).collect()
+    }
+
+    #[test]
+    fn ipv4_hosts() {
+        assert_eq!(hosts("10.0.0.0/30"),vec!["10.0.0.1","10.0.0.2"]);
+        let all = hosts("172.28.4.0/24");
+        assert_eq!(all.len(),254);
+        assert_eq!(all.first().map(String::as_str),Some("172.28.4.1"));
+        assert_eq!(all.last().map(String::as_str),Some("172.28.4.254"));
+    }
+
+    #[test]
+    fn ipv4_hosts_of_small_subnets() {
+        assert!(hosts("10.0.0.0/31").is_empty());
+        assert!(hosts("10.0.0.7/32").is_empty());
+    }
+
+    #[test]
+    fn ipv6_hosts() {
+        assert_eq!(hosts("fd00::/126"),vec!["fd00::1","fd00::2","fd00::3"]);
+        let mut hosts = "fd00::/64".parse::<Subnet>().unwrap().hosts();
+        assert_eq!(hosts.next(),Some("fd00::1".parse::<IpAddr>().unwrap()));
+    }
+}
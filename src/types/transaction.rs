@@ -0,0 +1,244 @@
+use mimir_crypto::secp256k1::{Address,Signer};
+use types::Error;
+use util;
+use tiny_keccak;
+
+
+/// An unsigned legacy transaction
+///
+/// Transactions are signed with EIP-155 replay protection, so the signed
+/// form is only valid on the chain with the supplied chain id.
+///
+#[derive(Debug,Clone)]
+pub struct Transaction {
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas: u64,
+    /// Recipient (`None` for contract creation)
+    pub to: Option<Address>,
+    /// Value transferred in wei (256-bit big-endian)
+    pub value: [u8;32],
+    pub data: Vec<u8>,
+}
+
+
+/// An RLP-encoded signed transaction, ready for `eth_sendRawTransaction`
+#[derive(Debug,Clone)]
+pub struct SignedTransaction {
+    pub raw: Vec<u8>,
+    pub hash: [u8;32],
+}
+
+
+impl Transaction {
+
+    /// Sign this transaction for the chain identified by `chain_id`.
+    pub fn sign(&self, signer: &Signer, chain_id: u64) -> Result<SignedTransaction,Error> {
+        let sighash = tiny_keccak::keccak256(&self.signing_payload(chain_id));
+        let signature = signer.sign_raw(&sighash)?.into_inner();
+        let (r,s,v) = (&signature[..32],&signature[32..64],signature[64]);
+        let recovery_id = u64::from(if v >= 27 { v - 27 } else { v });
+        let mut fields = self.fields();
+        fields.push(rlp::uint(eip155_v(chain_id,recovery_id)));
+        fields.push(rlp::bytes(trim_zeros(r)));
+        fields.push(rlp::bytes(trim_zeros(s)));
+        let raw = rlp::list(&fields);
+        let hash = tiny_keccak::keccak256(&raw);
+        Ok(SignedTransaction { raw, hash })
+    }
+
+    /// RLP-encoded payload whose hash is signed (per EIP-155).
+    fn signing_payload(&self, chain_id: u64) -> Vec<u8> {
+        let mut fields = self.fields();
+        fields.push(rlp::uint(chain_id));
+        fields.push(rlp::uint(0));
+        fields.push(rlp::uint(0));
+        rlp::list(&fields)
+    }
+
+    /// RLP-encoded fields common to the signing payload & the signed transaction.
+    fn fields(&self) -> Vec<Vec<u8>> {
+        let to = self.to.as_ref().map(|addr| addr.into_inner().to_vec()).unwrap_or_default();
+        vec![
+            rlp::uint(self.nonce),
+            rlp::uint(self.gas_price),
+            rlp::uint(self.gas),
+            rlp::bytes(&to),
+            rlp::bytes(trim_zeros(&self.value)),
+            rlp::bytes(&self.data),
+        ]
+    }
+}
+
+
+impl SignedTransaction {
+
+    /// Hex-encoded raw transaction (`0x`-prefixed).
+    pub fn raw_hex(&self) -> String { format!("0x{}",util::hex_string(&self.raw)) }
+
+    /// Hex-encoded transaction hash (`0x`-prefixed).
+    pub fn hash_hex(&self) -> String { format!("0x{}",util::hex_string(&self.hash)) }
+}
+
+
+/// Address of the contract created by `sender` at `nonce`.
+pub fn contract_address(sender: Address, nonce: u64) -> Result<Address,Error> {
+    let encoded = rlp::list(&[rlp::bytes(&sender.into_inner()),rlp::uint(nonce)]);
+    let hash = tiny_keccak::keccak256(&encoded);
+    let address = util::hex_string(&hash[12..]).parse()?;
+    Ok(address)
+}
+
+
+/// Signature `v` of a transaction replay-protected for `chain_id`.
+fn eip155_v(chain_id: u64, recovery_id: u64) -> u64 {
+    chain_id * 2 + 35 + recovery_id
+}
+
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+
+/// Minimal RLP encoding (sufficient for transactions).
+mod rlp {
+
+    pub fn bytes(data: &[u8]) -> Vec<u8> {
+        if data.len() == 1 && data[0] < 0x80 {
+            data.to_vec()
+        } else {
+            let mut encoded = prefix(0x80,data.len());
+            encoded.extend_from_slice(data);
+            encoded
+        }
+    }
+
+    pub fn uint(value: u64) -> Vec<u8> {
+        bytes(super::trim_zeros(&be_bytes(value)))
+    }
+
+    pub fn list(items: &[Vec<u8>]) -> Vec<u8> {
+        let payload: Vec<u8> = items.iter().flat_map(|item| item.iter().cloned()).collect();
+        let mut encoded = prefix(0xc0,payload.len());
+        encoded.extend(payload);
+        encoded
+    }
+
+    fn prefix(offset: u8, len: usize) -> Vec<u8> {
+        if len <= 55 {
+            vec![offset + len as u8]
+        } else {
+            let len_bytes = super::trim_zeros(&be_bytes(len as u64)).to_vec();
+            let mut encoded = vec![offset + 55 + len_bytes.len() as u8];
+            encoded.extend(len_bytes);
+            encoded
+        }
+    }
+
+    fn be_bytes(value: u64) -> [u8;8] {
+        let mut buf = [0u8;8];
+        for (index,byte) in buf.iter_mut().enumerate() {
+            *byte = (value >> (8 * (7 - index))) as u8;
+        }
+        buf
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::{Secret,Signer};
+    use util;
+    use tiny_keccak;
+    use super::*;
+
+    /// Example transaction of EIP-155.
+    fn eip155_example() -> Transaction {
+        let mut value = [0u8;32];
+        value[24..].copy_from_slice(&[0x0d,0xe0,0xb6,0xb3,0xa7,0x64,0x00,0x00]);
+        Transaction {
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas: 21000,
+            to: Some("3535353535353535353535353535353535353535".parse().unwrap()),
+            value: value,
+            data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rlp_empty_string() {
+        assert_eq!(rlp::bytes(&[]),vec![0x80]);
+        assert_eq!(rlp::uint(0),vec![0x80]);
+    }
+
+    #[test]
+    fn rlp_single_byte() {
+        assert_eq!(rlp::bytes(&[0x00]),vec![0x00]);
+        assert_eq!(rlp::bytes(&[0x7f]),vec![0x7f]);
+        assert_eq!(rlp::bytes(&[0x80]),vec![0x81,0x80]);
+        assert_eq!(rlp::uint(15),vec![0x0f]);
+        assert_eq!(rlp::uint(1024),vec![0x82,0x04,0x00]);
+    }
+
+    #[test]
+    fn rlp_long_string() {
+        let data = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        assert_eq!(data.len(),56);
+        let encoded = rlp::bytes(data);
+        assert_eq!(&encoded[..2],&[0xb8,0x38]);
+        assert_eq!(&encoded[2..],&data[..]);
+        let data = vec![0xaa;1024];
+        assert_eq!(&rlp::bytes(&data)[..3],&[0xb9,0x04,0x00]);
+    }
+
+    #[test]
+    fn rlp_lists() {
+        assert_eq!(rlp::list(&[]),vec![0xc0]);
+        let items = vec![rlp::bytes(b"cat"),rlp::bytes(b"dog")];
+        assert_eq!(rlp::list(&items),vec![0xc8,0x83,b'c',b'a',b't',0x83,b'd',b'o',b'g']);
+    }
+
+    #[test]
+    fn values_beyond_u64() {
+        // 100 ether
+        let mut tx = eip155_example();
+        tx.value = [0u8;32];
+        tx.value[23..].copy_from_slice(&[0x05,0x6b,0xc7,0x5e,0x2d,0x63,0x10,0x00,0x00]);
+        let encoded = hex_fields(&tx);
+        assert!(encoded.contains("89056bc75e2d6310000080"),"unexpected encoding: {}",encoded);
+    }
+
+    fn hex_fields(tx: &Transaction) -> String {
+        util::hex_string(&tx.fields().concat())
+    }
+
+    #[test]
+    fn eip155_v_values() {
+        assert_eq!(eip155_v(1,0),37);
+        assert_eq!(eip155_v(1,1),38);
+        assert_eq!(eip155_v(17,0),69);
+    }
+
+    #[test]
+    fn eip155_signing_hash() {
+        let payload = eip155_example().signing_payload(1);
+        assert_eq!(util::hex_string(&payload),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080");
+        assert_eq!(util::hex_string(&tiny_keccak::keccak256(&payload)),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53");
+    }
+
+    #[test]
+    fn eip155_signed_transaction() {
+        let secret: Secret = "4646464646464646464646464646464646464646464646464646464646464646".parse().unwrap();
+        let signer = Signer::new(secret).unwrap();
+        let signed = eip155_example().sign(&signer,1).unwrap();
+        assert_eq!(signed.raw_hex(),concat!(
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080",
+            "25a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+            "a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"));
+    }
+}